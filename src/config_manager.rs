@@ -1,8 +1,13 @@
 use std::fs;
 use std::path::PathBuf;
-use std::sync::RwLock;
-use std::time::SystemTime;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 use serde::{Deserialize, Serialize};
+use crate::gamepad::{GamepadBindings, GamepadButton, GamepadInput};
+use crate::prologue::ForeignDetourPolicy;
+use crate::wheel::HorizontalWheelAction;
+use crate::zoom_controller::{AnalogCurve, AnalogSource};
+use crate::logger;
 use windows::Win32::UI::Input::KeyboardAndMouse::*;
 
 /// YAML設定ファイル用の構造体
@@ -26,6 +31,78 @@ pub struct ZoomConfigYaml {
     /// スクロール1ノッチあたりのズーム変化量
     #[serde(default = "default_scroll_step")]
     pub scroll_step: f32,
+    /// ズームの指定方法 ("multiplier" または "target_fov")
+    #[serde(default = "default_zoom_mode")]
+    pub zoom_mode: String,
+    /// target_fov モードでの目標視野角（度）
+    #[serde(default = "default_target_fov_degrees")]
+    pub target_fov_degrees: f32,
+    /// ズーム中の最小視野角（度）
+    #[serde(default = "default_min_fov_degrees")]
+    pub min_fov_degrees: f32,
+    /// ズーム中はダッシュなどによるゲームのFOV変化を無視して視野角を固定するかどうか
+    #[serde(default = "default_lock_dynamic_fov")]
+    pub lock_dynamic_fov: bool,
+    /// 関数が見つからない場合に再試行する時間（秒, 0 で再試行しない）
+    #[serde(default = "default_hook_retry_timeout_seconds")]
+    pub hook_retry_timeout_seconds: f32,
+    /// 他のModが関数の先頭を書き換えていた場合の動作 ("chain" または "abort")
+    #[serde(default = "default_foreign_detour")]
+    pub foreign_detour: String,
+    /// チャットを開いている間はズームしないかどうか
+    #[serde(default = "default_suppress_in_chat")]
+    pub suppress_in_chat: bool,
+    /// インベントリやメニューなどの画面を開いている間はズームしないかどうか
+    #[serde(default = "default_suppress_in_menus")]
+    pub suppress_in_menus: bool,
+    /// チャットを開くキー名の一覧（チャットとメニューの区別に使用）
+    #[serde(default = "default_chat_keys")]
+    pub chat_keys: Vec<String>,
+    /// ズーム中のスクロールをゲームに渡さないかどうか
+    #[serde(default = "default_consume_scroll_while_zooming")]
+    pub consume_scroll_while_zooming: bool,
+    /// これより小さいスクロール量（ノッチ単位）は無視する
+    #[serde(default = "default_wheel_dead_zone")]
+    pub wheel_dead_zone: f32,
+    /// ズーム中の横スクロールで調整する項目 ("none" または "animation_speed")
+    #[serde(default = "default_horizontal_scroll_action")]
+    pub horizontal_scroll_action: String,
+    /// ゲームパッドのズームボタン
+    #[serde(default = "default_gamepad_zoom_button")]
+    pub gamepad_zoom_button: String,
+    /// ゲームパッドのズームを強めるボタン
+    #[serde(default = "default_gamepad_zoom_in_button")]
+    pub gamepad_zoom_in_button: String,
+    /// ゲームパッドのズームを弱めるボタン
+    #[serde(default = "default_gamepad_zoom_out_button")]
+    pub gamepad_zoom_out_button: String,
+    /// トリガーでズームする押し込みの深さ
+    #[serde(default = "default_gamepad_trigger_threshold")]
+    pub gamepad_trigger_threshold: f32,
+    /// アナログズームの入力 ("off", "trigger", "hold")
+    #[serde(default = "default_analog_zoom")]
+    pub analog_zoom: String,
+    /// アナログズームの曲線 ("linear", "ease_in", "ease_out", "smooth")
+    #[serde(default = "default_analog_curve")]
+    pub analog_curve: String,
+    /// hold で最大までズームするまでの秒数
+    #[serde(default = "default_analog_ramp_seconds")]
+    pub analog_ramp_seconds: f32,
+    /// 別のキーに割り当てるズームのプリセット
+    #[serde(default = "default_presets")]
+    pub presets: Vec<ZoomPresetYaml>,
+    /// zoom_key で切り替えるズーム倍率の段階
+    #[serde(default = "default_zoom_stops")]
+    pub zoom_stops: Vec<f32>,
+    /// ズームの段階を切り替えるキー名
+    #[serde(default = "default_zoom_stop_key")]
+    pub zoom_stop_key: String,
+    /// 最後の段階の次は最初の段階に戻るかどうか
+    #[serde(default = "default_zoom_stops_wrap")]
+    pub zoom_stops_wrap: bool,
+    /// ズームをやめたら最初の段階に戻るかどうか
+    #[serde(default = "default_zoom_stops_reset_on_release")]
+    pub zoom_stops_reset_on_release: bool,
 }
 
 fn default_zoom_key() -> String { "C".to_string() }
@@ -34,6 +111,30 @@ fn default_animation_speed() -> f32 { 0.1 }
 fn default_zoom_level() -> f32 { 10.0 }
 fn default_scroll_adjustment() -> bool { true }
 fn default_scroll_step() -> f32 { 1.0 }
+fn default_zoom_mode() -> String { "multiplier".to_string() }
+fn default_target_fov_degrees() -> f32 { 10.0 }
+fn default_min_fov_degrees() -> f32 { 1.0 }
+fn default_lock_dynamic_fov() -> bool { false }
+fn default_hook_retry_timeout_seconds() -> f32 { 30.0 }
+fn default_foreign_detour() -> String { "chain".to_string() }
+fn default_suppress_in_chat() -> bool { true }
+fn default_suppress_in_menus() -> bool { true }
+fn default_chat_keys() -> Vec<String> { vec!["T".to_string(), "SLASH".to_string()] }
+fn default_consume_scroll_while_zooming() -> bool { true }
+fn default_wheel_dead_zone() -> f32 { 0.1 }
+fn default_horizontal_scroll_action() -> String { "none".to_string() }
+fn default_gamepad_zoom_button() -> String { "none".to_string() }
+fn default_gamepad_zoom_in_button() -> String { "none".to_string() }
+fn default_gamepad_zoom_out_button() -> String { "none".to_string() }
+fn default_gamepad_trigger_threshold() -> f32 { 0.5 }
+fn default_analog_zoom() -> String { "off".to_string() }
+fn default_analog_curve() -> String { "linear".to_string() }
+fn default_analog_ramp_seconds() -> f32 { 0.5 }
+fn default_presets() -> Vec<ZoomPresetYaml> { Vec::new() }
+fn default_zoom_stops() -> Vec<f32> { Vec::new() }
+fn default_zoom_stop_key() -> String { "none".to_string() }
+fn default_zoom_stops_wrap() -> bool { true }
+fn default_zoom_stops_reset_on_release() -> bool { true }
 
 impl Default for ZoomConfigYaml {
     fn default() -> Self {
@@ -44,10 +145,123 @@ impl Default for ZoomConfigYaml {
             zoom_level: default_zoom_level(),
             scroll_adjustment: default_scroll_adjustment(),
             scroll_step: default_scroll_step(),
+            zoom_mode: default_zoom_mode(),
+            target_fov_degrees: default_target_fov_degrees(),
+            min_fov_degrees: default_min_fov_degrees(),
+            lock_dynamic_fov: default_lock_dynamic_fov(),
+            hook_retry_timeout_seconds: default_hook_retry_timeout_seconds(),
+            foreign_detour: default_foreign_detour(),
+            suppress_in_chat: default_suppress_in_chat(),
+            suppress_in_menus: default_suppress_in_menus(),
+            chat_keys: default_chat_keys(),
+            consume_scroll_while_zooming: default_consume_scroll_while_zooming(),
+            wheel_dead_zone: default_wheel_dead_zone(),
+            horizontal_scroll_action: default_horizontal_scroll_action(),
+            gamepad_zoom_button: default_gamepad_zoom_button(),
+            gamepad_zoom_in_button: default_gamepad_zoom_in_button(),
+            gamepad_zoom_out_button: default_gamepad_zoom_out_button(),
+            gamepad_trigger_threshold: default_gamepad_trigger_threshold(),
+            analog_zoom: default_analog_zoom(),
+            analog_curve: default_analog_curve(),
+            analog_ramp_seconds: default_analog_ramp_seconds(),
+            presets: default_presets(),
+            zoom_stops: default_zoom_stops(),
+            zoom_stop_key: default_zoom_stop_key(),
+            zoom_stops_wrap: default_zoom_stops_wrap(),
+            zoom_stops_reset_on_release: default_zoom_stops_reset_on_release(),
         }
     }
 }
 
+/// ズームの指定方法
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ZoomMode {
+    /// zoom_level の倍率でズーム
+    Multiplier,
+    /// target_fov_degrees の視野角になるようにズーム
+    TargetFov,
+}
+
+impl ZoomMode {
+    /// 設定値をズームの指定方法に変換（不明な値は倍率指定）
+    fn parse(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "target_fov" | "fov" => Self::TargetFov,
+            _ => Self::Multiplier,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Multiplier => "multiplier",
+            Self::TargetFov => "target_fov",
+        }
+    }
+}
+
+/// YAML設定ファイルのズームのプリセット（省略した項目は全体の設定を使用）
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ZoomPresetYaml {
+    /// ズームをトリガーするキー名
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zoom_level: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zoom_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_fov_degrees: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smooth_animation: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation_speed: Option<f32>,
+}
+
+/// ズームのプリセット (内部使用, None は全体の設定を使用)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZoomPreset {
+    pub key: i32,
+    pub zoom_level: Option<f32>,
+    pub zoom_mode: Option<ZoomMode>,
+    pub target_fov_degrees: Option<f32>,
+    pub smooth_animation: Option<bool>,
+    pub animation_speed: Option<f32>,
+}
+
+impl ZoomPreset {
+    fn from_yaml(yaml: &ZoomPresetYaml) -> Self {
+        Self {
+            key: ZoomConfig::parse_key(&yaml.key),
+            zoom_level: yaml.zoom_level.map(|level| level.clamp(1.0, 50.0)),
+            zoom_mode: yaml.zoom_mode.as_deref().map(ZoomMode::parse),
+            target_fov_degrees: yaml.target_fov_degrees.map(|fov| fov.clamp(1.0, 110.0)),
+            smooth_animation: yaml.smooth_animation,
+            animation_speed: yaml.animation_speed.map(|speed| speed.clamp(0.01, 1.0)),
+        }
+    }
+
+    fn to_yaml(&self) -> ZoomPresetYaml {
+        ZoomPresetYaml {
+            key: ZoomConfig::key_to_string(self.key),
+            zoom_level: self.zoom_level,
+            zoom_mode: self.zoom_mode.map(|mode| mode.as_str().to_string()),
+            target_fov_degrees: self.target_fov_degrees,
+            smooth_animation: self.smooth_animation,
+            animation_speed: self.animation_speed,
+        }
+    }
+}
+
+/// ズームの割り当て（zoom_key またはプリセットの設定を全体の設定で補ったもの）
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomBinding {
+    pub key: i32,
+    pub zoom_level: f32,
+    pub zoom_mode: ZoomMode,
+    pub target_fov_degrees: f32,
+    pub smooth_animation: bool,
+    pub animation_speed: f32,
+}
+
 /// Zoom機能の設定を管理する構造体 (内部使用)
 #[derive(Clone)]
 pub struct ZoomConfig {
@@ -63,6 +277,54 @@ pub struct ZoomConfig {
     pub scroll_adjustment: bool,
     /// スクロール1ノッチあたりのズーム変化量
     pub scroll_step: f32,
+    /// ズームの指定方法
+    pub zoom_mode: ZoomMode,
+    /// target_fov モードでの目標視野角（度）
+    pub target_fov_degrees: f32,
+    /// ズーム中の最小視野角（度）
+    pub min_fov_degrees: f32,
+    /// ズーム中はダッシュなどによるゲームのFOV変化を無視して視野角を固定するかどうか
+    pub lock_dynamic_fov: bool,
+    /// 関数が見つからない場合に再試行する時間（秒）
+    pub hook_retry_timeout_seconds: f32,
+    /// 他のModが関数の先頭を書き換えていた場合の動作
+    pub foreign_detour: ForeignDetourPolicy,
+    /// チャットを開いている間はズームしないかどうか
+    pub suppress_in_chat: bool,
+    /// インベントリやメニューなどの画面を開いている間はズームしないかどうか
+    pub suppress_in_menus: bool,
+    /// チャットを開くキーコードの一覧
+    pub chat_keys: Vec<i32>,
+    /// ズーム中のスクロールをゲームに渡さないかどうか
+    pub consume_scroll_while_zooming: bool,
+    /// これより小さいスクロール量（ノッチ単位）は無視する
+    pub wheel_dead_zone: f32,
+    /// ズーム中の横スクロールで調整する項目
+    pub horizontal_scroll_action: HorizontalWheelAction,
+    /// ゲームパッドのズームボタン（None は割り当てなし）
+    pub gamepad_zoom_button: Option<GamepadInput>,
+    /// ゲームパッドのズームを強めるボタン
+    pub gamepad_zoom_in_button: Option<GamepadButton>,
+    /// ゲームパッドのズームを弱めるボタン
+    pub gamepad_zoom_out_button: Option<GamepadButton>,
+    /// トリガーでズームする押し込みの深さ (0.0 ~ 1.0)
+    pub gamepad_trigger_threshold: f32,
+    /// アナログズームの入力
+    pub analog_zoom: AnalogSource,
+    /// アナログズームの曲線
+    pub analog_curve: AnalogCurve,
+    /// hold で最大までズームするまでの秒数 (0.0 ~ 10.0)
+    pub analog_ramp_seconds: f32,
+    /// 別のキーに割り当てるズームのプリセット
+    pub presets: Vec<ZoomPreset>,
+    /// zoom_key で切り替えるズーム倍率の段階（空なら zoom_level を使用）
+    pub zoom_stops: Vec<f32>,
+    /// ズームの段階を切り替えるキーコード（None は zoom_key で切り替え）
+    pub zoom_stop_key: Option<i32>,
+    /// 最後の段階の次は最初の段階に戻るかどうか
+    pub zoom_stops_wrap: bool,
+    /// ズームをやめたら最初の段階に戻るかどうか
+    pub zoom_stops_reset_on_release: bool,
 }
 
 impl Default for ZoomConfig {
@@ -74,6 +336,30 @@ impl Default for ZoomConfig {
             zoom_level: 10.0,
             scroll_adjustment: true,
             scroll_step: 1.0,
+            zoom_mode: ZoomMode::Multiplier,
+            target_fov_degrees: 10.0,
+            min_fov_degrees: 1.0,
+            lock_dynamic_fov: false,
+            hook_retry_timeout_seconds: 30.0,
+            foreign_detour: ForeignDetourPolicy::Chain,
+            suppress_in_chat: true,
+            suppress_in_menus: true,
+            chat_keys: vec![VK_T.0 as i32, VK_OEM_2.0 as i32],
+            consume_scroll_while_zooming: true,
+            wheel_dead_zone: 0.1,
+            horizontal_scroll_action: HorizontalWheelAction::None,
+            gamepad_zoom_button: None,
+            gamepad_zoom_in_button: None,
+            gamepad_zoom_out_button: None,
+            gamepad_trigger_threshold: 0.5,
+            analog_zoom: AnalogSource::Off,
+            analog_curve: AnalogCurve::Linear,
+            analog_ramp_seconds: 0.5,
+            presets: Vec::new(),
+            zoom_stops: Vec::new(),
+            zoom_stop_key: None,
+            zoom_stops_wrap: true,
+            zoom_stops_reset_on_release: true,
         }
     }
 }
@@ -87,25 +373,93 @@ impl From<ZoomConfigYaml> for ZoomConfig {
             zoom_level: yaml.zoom_level.clamp(1.0, 50.0),
             scroll_adjustment: yaml.scroll_adjustment,
             scroll_step: yaml.scroll_step.clamp(0.1, 10.0),
+            zoom_mode: ZoomMode::parse(&yaml.zoom_mode),
+            target_fov_degrees: yaml.target_fov_degrees.clamp(1.0, 110.0),
+            min_fov_degrees: yaml.min_fov_degrees.clamp(0.1, 60.0),
+            lock_dynamic_fov: yaml.lock_dynamic_fov,
+            hook_retry_timeout_seconds: yaml.hook_retry_timeout_seconds.clamp(0.0, 300.0),
+            foreign_detour: ForeignDetourPolicy::parse(&yaml.foreign_detour),
+            suppress_in_chat: yaml.suppress_in_chat,
+            suppress_in_menus: yaml.suppress_in_menus,
+            chat_keys: yaml.chat_keys.iter().map(|key| Self::parse_key(key)).collect(),
+            consume_scroll_while_zooming: yaml.consume_scroll_while_zooming,
+            wheel_dead_zone: yaml.wheel_dead_zone.clamp(0.0, 1.0),
+            horizontal_scroll_action: HorizontalWheelAction::parse(&yaml.horizontal_scroll_action),
+            gamepad_zoom_button: GamepadInput::parse(&yaml.gamepad_zoom_button),
+            gamepad_zoom_in_button: GamepadButton::parse(&yaml.gamepad_zoom_in_button),
+            gamepad_zoom_out_button: GamepadButton::parse(&yaml.gamepad_zoom_out_button),
+            gamepad_trigger_threshold: yaml.gamepad_trigger_threshold.clamp(0.0, 1.0),
+            analog_zoom: AnalogSource::parse(&yaml.analog_zoom),
+            analog_curve: AnalogCurve::parse(&yaml.analog_curve),
+            analog_ramp_seconds: yaml.analog_ramp_seconds.clamp(0.0, 10.0),
+            presets: yaml.presets.iter().map(ZoomPreset::from_yaml).collect(),
+            zoom_stops: yaml.zoom_stops.iter().map(|stop| stop.clamp(1.0, 50.0)).collect(),
+            zoom_stop_key: Some(yaml.zoom_stop_key.as_str()).filter(|key| !key.eq_ignore_ascii_case("none")).map(Self::parse_key),
+            zoom_stops_wrap: yaml.zoom_stops_wrap,
+            zoom_stops_reset_on_release: yaml.zoom_stops_reset_on_release,
         }
     }
 }
 
 impl ZoomConfig {
+    /// ズームの割り当ての一覧（0 番目は zoom_key, 以降は presets の順）
+    pub fn bindings(&self) -> Vec<ZoomBinding> {
+        let primary = ZoomBinding {
+            key: self.zoom_key,
+            zoom_level: self.zoom_level,
+            zoom_mode: self.zoom_mode,
+            target_fov_degrees: self.target_fov_degrees,
+            smooth_animation: self.smooth_animation,
+            animation_speed: self.animation_speed,
+        };
+        let presets = self.presets.iter().map(|preset| ZoomBinding {
+            key: preset.key,
+            zoom_level: preset.zoom_level.unwrap_or(primary.zoom_level),
+            zoom_mode: preset.zoom_mode.unwrap_or(primary.zoom_mode),
+            target_fov_degrees: preset.target_fov_degrees.unwrap_or(primary.target_fov_degrees),
+            smooth_animation: preset.smooth_animation.unwrap_or(primary.smooth_animation),
+            animation_speed: preset.animation_speed.unwrap_or(primary.animation_speed),
+        });
+        std::iter::once(primary).chain(presets).collect()
+    }
+
+    /// ゲームパッドの割り当て
+    pub fn gamepad_bindings(&self) -> GamepadBindings {
+        GamepadBindings {
+            zoom: self.gamepad_zoom_button,
+            zoom_in: self.gamepad_zoom_in_button,
+            zoom_out: self.gamepad_zoom_out_button,
+            trigger_threshold: self.gamepad_trigger_threshold,
+        }
+    }
+
+    /// 割り当てのズーム倍率を変更（プリセットは他の割り当てに影響しないよう個別に保持する）
+    pub fn set_binding_zoom_level(&mut self, index: usize, zoom_level: f32) {
+        match index.checked_sub(1).and_then(|preset| self.presets.get_mut(preset)) {
+            Some(preset) => preset.zoom_level = Some(zoom_level),
+            None => self.zoom_level = zoom_level,
+        }
+    }
+
+    /// 割り当ての目標視野角を変更
+    pub fn set_binding_target_fov(&mut self, index: usize, target_fov_degrees: f32) {
+        match index.checked_sub(1).and_then(|preset| self.presets.get_mut(preset)) {
+            Some(preset) => preset.target_fov_degrees = Some(target_fov_degrees),
+            None => self.target_fov_degrees = target_fov_degrees,
+        }
+    }
+
+    /// 割り当てのアニメーション速度を変更
+    pub fn set_binding_animation_speed(&mut self, index: usize, animation_speed: f32) {
+        match index.checked_sub(1).and_then(|preset| self.presets.get_mut(preset)) {
+            Some(preset) => preset.animation_speed = Some(animation_speed),
+            None => self.animation_speed = animation_speed,
+        }
+    }
+
     /// 設定ファイルのパスを取得 (mods/Zoom/config.yml)
     pub fn config_path() -> PathBuf {
-        let mut path = std::env::current_exe().unwrap_or_default();
-        path.pop(); // 実行ファイルのディレクトリ
-        path.push("mods");
-        path.push("Zoom");
-        
-        // ディレクトリが存在しない場合は作成
-        if !path.exists() {
-            let _ = fs::create_dir_all(&path);
-        }
-        
-        path.push("config.yml");
-        path
+        crate::paths::mod_file("config.yml")
     }
 
     /// 設定をファイルから読み込む
@@ -148,12 +502,37 @@ impl ZoomConfig {
             zoom_level: self.zoom_level,
             scroll_adjustment: self.scroll_adjustment,
             scroll_step: self.scroll_step,
+            zoom_mode: self.zoom_mode.as_str().to_string(),
+            target_fov_degrees: self.target_fov_degrees,
+            min_fov_degrees: self.min_fov_degrees,
+            lock_dynamic_fov: self.lock_dynamic_fov,
+            hook_retry_timeout_seconds: self.hook_retry_timeout_seconds,
+            foreign_detour: self.foreign_detour.as_str().to_string(),
+            suppress_in_chat: self.suppress_in_chat,
+            suppress_in_menus: self.suppress_in_menus,
+            chat_keys: self.chat_keys.iter().map(|&key| Self::key_to_string(key)).collect(),
+            consume_scroll_while_zooming: self.consume_scroll_while_zooming,
+            wheel_dead_zone: self.wheel_dead_zone,
+            horizontal_scroll_action: self.horizontal_scroll_action.as_str().to_string(),
+            gamepad_zoom_button: GamepadInput::to_config(self.gamepad_zoom_button),
+            gamepad_zoom_in_button: self.gamepad_zoom_in_button.map_or("none", GamepadButton::as_str).to_string(),
+            gamepad_zoom_out_button: self.gamepad_zoom_out_button.map_or("none", GamepadButton::as_str).to_string(),
+            gamepad_trigger_threshold: self.gamepad_trigger_threshold,
+            analog_zoom: self.analog_zoom.as_str().to_string(),
+            analog_curve: self.analog_curve.as_str().to_string(),
+            analog_ramp_seconds: self.analog_ramp_seconds,
+            presets: self.presets.iter().map(ZoomPreset::to_yaml).collect(),
+            zoom_stops: self.zoom_stops.clone(),
+            zoom_stop_key: self.zoom_stop_key.map_or_else(|| "none".to_string(), Self::key_to_string),
+            zoom_stops_wrap: self.zoom_stops_wrap,
+            zoom_stops_reset_on_release: self.zoom_stops_reset_on_release,
         };
         
         let header = r#"# Zoom Configuration File / ズーム設定ファイル
 #
 # zoom_key: ズームをトリガーするキー
-#   使用可能なキー名: A-Z, 0-9, F1-F12, CTRL, SHIFT, ALT, SPACE, TAB, ENTER, ESC
+#   使用可能なキー名: A-Z, 0-9, F1-F12, CTRL, SHIFT, ALT, SPACE, TAB, ENTER, ESC, SLASH
+#     マウスボタン: MOUSE3（中ボタン）, MOUSE4, MOUSE5（サイドボタン）
 #   例: "C", "Z", "F5", "CTRL"
 #
 # smooth_animation: スムーズズームアニメーション
@@ -167,14 +546,109 @@ impl ZoomConfig {
 #
 # scroll_adjustment: マウススクロールでズーム倍率を調整
 #   true: ズーム中にスクロールで倍率変更可能
-#   false: スクロール調整を無効化
+#   false: スクロール調整を無効化（横スクロールやマウスボタンも使わない場合はマウスフックを設定しない）
 #
 # scroll_step: スクロール1ノッチあたりのズーム変化量 (0.1 ~ 10.0)
+#   target_fov モードでは1ノッチあたりの視野角の変化量（度）
+#
+# zoom_mode: ズームの指定方法
+#   multiplier: zoom_level の倍率でズーム
+#   target_fov: 視野角が target_fov_degrees になるようにズーム
+#
+# target_fov_degrees: target_fov モードでの目標視野角（度, 1.0 ~ 110.0）
+#
+# min_fov_degrees: ズーム中の最小視野角（度, 0.1 ~ 60.0）
+#   どのモードでもこれより狭くはズームしない
+#
+# lock_dynamic_fov: ズーム中の視野角を固定
+#   true: ダッシュや移動速度上昇、望遠鏡によるFOVの変化を無視し、ズーム開始前のFOVを基準にする
+#   false: ゲームの現在のFOVに倍率をかける
+#
+# hook_retry_timeout_seconds: 起動時に renderLevel が見つからない場合に再試行する時間（秒, 0.0 ~ 300.0）
+#   ゲームの展開が終わる前に検索した場合に備えて、間隔を延ばしながら再試行する
+#   0 にすると再試行しない
+#
+# foreign_detour: renderLevel の先頭が他のModによってジャンプに書き換えられていた場合の動作
+#   chain: 警告を出してその上からフックする（両方のModが動作する）
+#   abort: フックせずにズームを無効にする
+#
+# suppress_in_chat: チャットを開いている間はズームキーを無視する
+#   true: チャットで文字を入力してもズームしない
+#   false: チャット中もズームキーが有効
+#
+# suppress_in_menus: インベントリ、メニュー、看板などの画面を開いている間はズームキーを無視する
+#   画面が開いているかはマウスカーソルの表示で判定する
+#
+# chat_keys: チャットを開くキー（ゲームのキー設定を変更した場合に合わせる）
+#   プレイ中にこのキーを押した直後に開いた画面をチャットとみなす
+#   例: ["T", "SLASH"]
+#
+# consume_scroll_while_zooming: ズーム倍率を調整したスクロールをゲームに渡さない
+#   true: ズーム中にスクロールしてもホットバーが切り替わらない
+#   false: 倍率の調整と同時にホットバーも切り替わる
+#   scroll_adjustment が false の場合は常にゲームに渡す
+#
+# wheel_dead_zone: スクロールの不感帯（ノッチ単位, 0.0 ~ 1.0）
+#   高精度のホイールやタッチパッドの細かいスクロールは蓄積し、これを超えた分だけ調整する
+#   0.0 にするとわずかなスクロールもすぐに反映する
+#
+# horizontal_scroll_action: ズーム中の横スクロール（チルトホイール・タッチパッド）で調整する項目
+#   none: 何もしない（ゲームにそのまま渡す）
+#   animation_speed: 右でアニメーションを速く、左で遅くする
+#
+# gamepad_zoom_button: 押している間ズームするゲームパッドのボタン（XInput 対応のコントローラー）
+#   none: 使用しない
+#   A, B, X, Y, LEFT_SHOULDER, RIGHT_SHOULDER, LEFT_THUMB, RIGHT_THUMB, START, BACK, DPAD_UP など
+#   LEFT_TRIGGER, RIGHT_TRIGGER: トリガーを gamepad_trigger_threshold 以上押し込んでいる間ズーム
+#
+# gamepad_zoom_in_button / gamepad_zoom_out_button: ズーム中に押すとズームを強める・弱めるボタン
+#   1回押すとスクロール1ノッチ分調整する（scroll_adjustment が true の場合のみ）
+#   none: 使用しない
+#
+# gamepad_trigger_threshold: トリガーに割り当てた場合にズームする押し込みの深さ (0.0 ~ 1.0)
+#
+# analog_zoom: ズームの強さを入力に合わせて連続的に変える
+#   off: 押している間は常に zoom_level（または target_fov_degrees）までズーム
+#   trigger: gamepad_zoom_button のトリガーの押し込みの深さに応じてズーム（キーボードで押した場合は最大）
+#   hold: 押し続けた時間に応じて analog_ramp_seconds かけて最大までズーム
+#
+# analog_curve: 入力の量からズームの強さへの変換
+#   linear: 比例
+#   ease_in: 最初はゆっくり、最後に大きくズーム
+#   ease_out: 最初に大きく、最後はゆっくりズーム
+#   smooth: 始まりと終わりをゆっくり
+#
+# analog_ramp_seconds: analog_zoom が hold の場合に最大までズームするまでの秒数 (0.0 ~ 10.0)
+#
+# presets: zoom_key とは別のキーで使うズームの一覧（例: 軽く覗く 3 倍と遠くを見る 20 倍）
+#   key: ズームするキー（必須）
+#   zoom_level, zoom_mode, target_fov_degrees, smooth_animation, animation_speed: 省略した項目は上の設定を使う
+#   複数のキーを押している場合は最後に押したものを使い、離すと押したままのキーに戻る
+#   ズーム中のスクロールなどで調整した値はそのプリセットに保存する
+#   例:
+#   presets:
+#     - key: V
+#       zoom_level: 3.0
+#     - key: B
+#       zoom_level: 20.0
+#       animation_speed: 0.05
+#
+# zoom_stops: zoom_key のズームを決まった倍率の段階で切り替える（例: [2.0, 4.0, 8.0, 16.0]）
+#   空の場合は zoom_level を使う
+#   指定した場合は zoom_mode に関わらず倍率で指定し、スクロールやゲームパッドのボタンは1ノッチで1段階切り替える
+#
+# zoom_stop_key: ズーム中に押すたびに次の段階に切り替えるキー
+#   none: zoom_key を押し直すたびに次の段階に切り替える
+#
+# zoom_stops_wrap: 最後の段階の次は最初の段階に戻る（false の場合は最後の段階で止まる）
+#
+# zoom_stops_reset_on_release: ズームをやめたら最初の段階に戻る（false の場合は前回の段階を覚えておく）
+#   zoom_stop_key が none の場合、離してから 0.4 秒以内に押し直すと戻らずに次の段階に進む
 
 "#;
         
         let yaml_content = serde_yaml::to_string(&yaml_config)
-            .unwrap_or_else(|_| "zoom_key: C\nsmooth_animation: true\nanimation_speed: 0.1\nzoom_level: 10.0\nscroll_adjustment: true\nscroll_step: 1.0\n".to_string());
+            .unwrap_or_else(|_| "zoom_key: C\nsmooth_animation: true\nanimation_speed: 0.1\nzoom_level: 10.0\nscroll_adjustment: true\nscroll_step: 1.0\nzoom_mode: multiplier\ntarget_fov_degrees: 10.0\nmin_fov_degrees: 1.0\nlock_dynamic_fov: false\nhook_retry_timeout_seconds: 30.0\nforeign_detour: chain\nsuppress_in_chat: true\nsuppress_in_menus: true\nchat_keys:\n- T\n- SLASH\nconsume_scroll_while_zooming: true\nwheel_dead_zone: 0.1\nhorizontal_scroll_action: none\ngamepad_zoom_button: none\ngamepad_zoom_in_button: none\ngamepad_zoom_out_button: none\ngamepad_trigger_threshold: 0.5\nanalog_zoom: off\nanalog_curve: linear\nanalog_ramp_seconds: 0.5\npresets: []\nzoom_stops: []\nzoom_stop_key: none\nzoom_stops_wrap: true\nzoom_stops_reset_on_release: true\n".to_string());
         
         fs::write(path, format!("{}{}", header, yaml_content))
     }
@@ -250,6 +724,12 @@ impl ZoomConfig {
             "ENTER" | "RETURN" => VK_RETURN.0 as i32,
             "ESCAPE" | "ESC" => VK_ESCAPE.0 as i32,
             "BACKSPACE" | "BACK" => VK_BACK.0 as i32,
+            "SLASH" | "/" => VK_OEM_2.0 as i32,
+            
+            // マウスボタン
+            "MOUSE3" | "MBUTTON" | "MIDDLE" => VK_MBUTTON.0 as i32,
+            "MOUSE4" | "XBUTTON1" => VK_XBUTTON1.0 as i32,
+            "MOUSE5" | "XBUTTON2" => VK_XBUTTON2.0 as i32,
             
             // 数字キー
             "0" => 0x30,
@@ -316,6 +796,10 @@ impl ZoomConfig {
             x if x == VK_TAB.0 as i32 => "TAB".to_string(),
             x if x == VK_RETURN.0 as i32 => "ENTER".to_string(),
             x if x == VK_ESCAPE.0 as i32 => "ESC".to_string(),
+            x if x == VK_OEM_2.0 as i32 => "SLASH".to_string(),
+            x if x == VK_MBUTTON.0 as i32 => "MOUSE3".to_string(),
+            x if x == VK_XBUTTON1.0 as i32 => "MOUSE4".to_string(),
+            x if x == VK_XBUTTON2.0 as i32 => "MOUSE5".to_string(),
             x if x >= 0x30 && x <= 0x39 => ((x - 0x30) as u8 + b'0').to_string(),
             _ => format!("0x{:02X}", key_code),
         }
@@ -325,6 +809,11 @@ impl ZoomConfig {
 // グローバル設定インスタンス
 static CONFIG: RwLock<Option<ZoomConfig>> = RwLock::new(None);
 static LAST_MODIFIED: RwLock<Option<SystemTime>> = RwLock::new(None);
+// まだファイルに書き込んでいない設定と、最後に変更した時刻
+static PENDING_SAVE: Mutex<Option<(ZoomConfig, Instant)>> = Mutex::new(None);
+
+/// スクロールなどで連続した変更をまとめて保存するまでの待ち時間
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
 
 /// 設定ファイルの更新時刻を取得
 fn get_file_modified_time() -> Option<SystemTime> {
@@ -376,3 +865,79 @@ pub fn get_config() -> ZoomConfig {
 pub fn reload_config() -> ZoomConfig {
     init_config()
 }
+
+/// 設定をすぐに反映し、ファイルへの保存は変更が落ち着くまで遅らせる
+pub fn save_deferred(config: ZoomConfig) {
+    if let Ok(mut guard) = CONFIG.write() {
+        *guard = Some(config.clone());
+    }
+    if let Ok(mut pending) = PENDING_SAVE.lock() {
+        *pending = Some((config, Instant::now()));
+    }
+}
+
+/// 最後の変更から待ち時間が過ぎていれば保留中の設定を保存
+pub fn flush_due_save() {
+    flush_save(false);
+}
+
+/// 保留中の設定をすぐに保存（終了時用）
+pub fn flush_pending_save() {
+    flush_save(true);
+}
+
+/// プロセス終了時に保留中の設定を保存する
+///
+/// 停止済みのスレッドがロックを持ったままの可能性があるため、ロックを待たずログも出さない。
+pub fn flush_pending_save_on_exit() {
+    if let Ok(mut pending) = PENDING_SAVE.try_lock() {
+        if let Some((config, _)) = pending.take() {
+            let _ = config.save();
+        }
+    }
+}
+
+fn flush_save(force: bool) {
+    let config = match PENDING_SAVE.lock() {
+        Ok(mut pending) => match pending.as_ref() {
+            Some((_, changed_at)) if force || changed_at.elapsed() >= SAVE_DEBOUNCE => {
+                pending.take().map(|(config, _)| config)
+            }
+            _ => None,
+        },
+        Err(_) => None,
+    };
+    let Some(config) = config else {
+        return;
+    };
+
+    if let Err(error) = config.save() {
+        logger::warn(&format!("failed to save config: {}", error));
+        return;
+    }
+    // 自分で書き込んだ変更は再読み込みしない（その間の変更を古い値で上書きしないため）
+    if let Ok(mut guard) = LAST_MODIFIED.write() {
+        *guard = get_file_modified_time();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_does_not_read_the_gamepad() {
+        assert!(ZoomConfig::default().gamepad_bindings().is_unbound());
+        assert!(ZoomConfig::from(ZoomConfigYaml::default()).gamepad_bindings().is_unbound());
+        let missing_keys: ZoomConfigYaml = serde_yaml::from_str("zoom_key: C\n").unwrap();
+        assert!(ZoomConfig::from(missing_keys).gamepad_bindings().is_unbound());
+    }
+
+    #[test]
+    fn any_gamepad_button_binds_the_gamepad() {
+        let yaml: ZoomConfigYaml = serde_yaml::from_str("gamepad_zoom_in_button: DPAD_UP\n").unwrap();
+        let bindings = ZoomConfig::from(yaml).gamepad_bindings();
+        assert!(!bindings.is_unbound());
+        assert_eq!(bindings.zoom_in, Some(GamepadButton::DpadUp));
+    }
+}
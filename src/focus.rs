@@ -0,0 +1,36 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// ゲームのウィンドウが入力を受け取っているかの判定
+///
+/// Windows ではフォアグラウンドウィンドウのプロセスで判定し、テストでは任意の状態を返す。
+pub trait FocusProvider: Send + Sync {
+    fn is_game_focused(&self) -> bool;
+}
+
+/// 状態を外から切り替えられる判定（テスト用）
+#[derive(Debug, Default)]
+pub struct FakeFocus {
+    focused: AtomicBool,
+}
+
+impl FakeFocus {
+    pub const fn new(focused: bool) -> Self {
+        Self { focused: AtomicBool::new(focused) }
+    }
+
+    pub fn set(&self, focused: bool) {
+        self.focused.store(focused, Ordering::Relaxed);
+    }
+}
+
+impl FocusProvider for FakeFocus {
+    fn is_game_focused(&self) -> bool {
+        self.focused.load(Ordering::Relaxed)
+    }
+}
+
+impl<F: FocusProvider + ?Sized> FocusProvider for &F {
+    fn is_game_focused(&self) -> bool {
+        (**self).is_game_focused()
+    }
+}
@@ -0,0 +1,43 @@
+use std::mem::size_of;
+
+use windows::Win32::System::Threading::GetCurrentProcessId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetCursorInfo, GetForegroundWindow, GetWindowThreadProcessId, CURSORINFO, CURSOR_SHOWING,
+};
+
+use crate::focus::FocusProvider;
+
+/// フォアグラウンドウィンドウがゲームのプロセスのものかで判定する
+pub struct ForegroundFocus;
+
+impl ForegroundFocus {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ForegroundFocus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FocusProvider for ForegroundFocus {
+    fn is_game_focused(&self) -> bool {
+        unsafe {
+            let window = GetForegroundWindow();
+            if window.0 == 0 {
+                return false;
+            }
+            let mut process_id = 0u32;
+            GetWindowThreadProcessId(window, Some(&mut process_id));
+            process_id == GetCurrentProcessId()
+        }
+    }
+}
+
+/// マウスカーソルが表示されているか（ゲームは画面を開いている間だけ表示する）
+pub fn is_cursor_visible() -> bool {
+    let mut info = CURSORINFO { cbSize: size_of::<CURSORINFO>() as u32, ..Default::default() };
+    unsafe { GetCursorInfo(&mut info).is_ok() && info.flags.0 & CURSOR_SHOWING.0 != 0 }
+}
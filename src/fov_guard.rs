@@ -0,0 +1,302 @@
+use std::fmt;
+
+use crate::fov_math::scale_to_degrees;
+
+/// 読み取ったFOVとして書き換えてよい角度の範囲（度、望遠鏡は垂直 7 度前後まで狭める）
+const MIN_BASE_FOV_DEGREES: f32 = 1.0;
+const MAX_BASE_FOV_DEGREES: f32 = 170.0;
+/// 書き込むFOVとして妥当な角度の範囲（度）
+const MIN_WRITTEN_FOV_DEGREES: f32 = 0.05;
+const MAX_WRITTEN_FOV_DEGREES: f32 = 179.0;
+/// fov_y / fov_x から求まるアスペクト比の妥当な範囲
+const MIN_ASPECT_RATIO: f32 = 0.2;
+const MAX_ASPECT_RATIO: f32 = 5.0;
+/// 1フレームでの変化量として許容する倍率（超えたフレームは書き換えずに見送る）
+const MAX_FRAME_CHANGE: f32 = 2.0;
+/// この回数だけ連続で異常な値を読んだらセーフモードに移行する
+const SAFE_MODE_THRESHOLD: u32 = 120;
+
+/// FOVの値が妥当でない理由
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FovProblem {
+    /// NaN や無限大、0以下の値
+    NotFinite { fov_x: f32, fov_y: f32 },
+    /// 角度に換算すると範囲外
+    OutOfRange { degrees: f32 },
+    /// 水平・垂直の比がアスペクト比としてありえない
+    AspectRatio { ratio: f32 },
+    /// 前フレームから急激に変化した
+    Unstable { previous: f32, current: f32 },
+    /// 書き込んだ値と読み戻した値が一致しない
+    WriteMismatch { expected: f32, actual: f32 },
+}
+
+impl fmt::Display for FovProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFinite { fov_x, fov_y } => {
+                write!(f, "non-finite or non-positive values (fov_x={}, fov_y={})", fov_x, fov_y)
+            }
+            Self::OutOfRange { degrees } => write!(f, "FOV of {:.2} degrees is out of range", degrees),
+            Self::AspectRatio { ratio } => write!(f, "implied aspect ratio {:.3} is implausible", ratio),
+            Self::Unstable { previous, current } => {
+                write!(f, "value jumped from {} to {} in one frame", previous, current)
+            }
+            Self::WriteMismatch { expected, actual } => {
+                write!(f, "wrote {} but read back {}", expected, actual)
+            }
+        }
+    }
+}
+
+/// 検査結果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GuardVerdict {
+    /// 書き込んでよい
+    Plausible,
+    /// このフレームは書き込まない
+    Skip(FovProblem),
+    /// 異常が続いたためフックを無効化すべき
+    SafeMode(FovProblem),
+}
+
+/// LevelRendererPlayer のFOVフィールドを書き込む前後の妥当性チェック
+///
+/// fov_x / fov_y は投影行列のスケール (1 / tan(fov / 2)) を保持している。
+/// ゲームのバージョンとオフセットが合っていないと別のメモリを指すため、
+/// 値が妥当でない間は書き込まず、異常が続く場合はセーフモードへ移行させる。
+pub struct FovGuard {
+    previous: Option<(f32, f32)>,
+    implausible_frames: u32,
+    tripped: bool,
+}
+
+impl Default for FovGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FovGuard {
+    pub const fn new() -> Self {
+        Self { previous: None, implausible_frames: 0, tripped: false }
+    }
+
+    /// 前フレームの記録を破棄（プレイヤーが切り替わった場合など）
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.implausible_frames = 0;
+    }
+
+    /// セーフモードに移行済みかどうか
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// 連続して異常な値を読んだフレーム数
+    pub fn implausible_frames(&self) -> u32 {
+        self.implausible_frames
+    }
+
+    /// ゲームが設定したFOVを書き換える前に検査する
+    ///
+    /// 値として壊れている場合だけセーフモードへの回数に数える。
+    /// 望遠鏡などで角度が範囲外になったり急に変わったりした場合は、そのフレームを見送るだけにする。
+    pub fn check_read(&mut self, fov_x: f32, fov_y: f32) -> GuardVerdict {
+        let garbage = Self::garbage(fov_x, fov_y);
+        let skipped = Self::out_of_range(fov_x, fov_y, MIN_BASE_FOV_DEGREES, MAX_BASE_FOV_DEGREES)
+            .or_else(|| self.unstable(fov_x, fov_y));
+
+        if fov_x.is_finite() && fov_y.is_finite() {
+            self.previous = Some((fov_x, fov_y));
+        }
+        match (garbage, skipped) {
+            // 妥当でも書き込みの検査が終わるまでは連続カウントを維持する
+            (Some(problem), _) => self.verdict(Some(problem), false),
+            (None, Some(problem)) => GuardVerdict::Skip(problem),
+            (None, None) => self.verdict(None, false),
+        }
+    }
+
+    /// 書き込み後に読み戻した値を検査する
+    pub fn check_written(&mut self, expected: (f32, f32), actual: (f32, f32)) -> GuardVerdict {
+        let problem = [(expected.0, actual.0), (expected.1, actual.1)]
+            .into_iter()
+            .find(|(expected, actual)| expected.to_bits() != actual.to_bits())
+            .map(|(expected, actual)| FovProblem::WriteMismatch { expected, actual })
+            .or_else(|| {
+                Self::validate(actual.0, actual.1, MIN_WRITTEN_FOV_DEGREES, MAX_WRITTEN_FOV_DEGREES)
+            });
+        self.verdict(problem, true)
+    }
+
+    fn verdict(&mut self, problem: Option<FovProblem>, frame_complete: bool) -> GuardVerdict {
+        let Some(problem) = problem else {
+            if frame_complete {
+                self.implausible_frames = 0;
+            }
+            return GuardVerdict::Plausible;
+        };
+
+        self.implausible_frames += 1;
+        if self.implausible_frames >= SAFE_MODE_THRESHOLD {
+            self.tripped = true;
+            GuardVerdict::SafeMode(problem)
+        } else {
+            GuardVerdict::Skip(problem)
+        }
+    }
+
+    fn validate(fov_x: f32, fov_y: f32, min_degrees: f32, max_degrees: f32) -> Option<FovProblem> {
+        Self::garbage(fov_x, fov_y).or_else(|| Self::out_of_range(fov_x, fov_y, min_degrees, max_degrees))
+    }
+
+    /// オフセットが別のメモリを指しているときのような、FOVとしてありえない値
+    fn garbage(fov_x: f32, fov_y: f32) -> Option<FovProblem> {
+        if !(fov_x.is_finite() && fov_y.is_finite() && fov_x > 0.0 && fov_y > 0.0) {
+            return Some(FovProblem::NotFinite { fov_x, fov_y });
+        }
+
+        let ratio = fov_y / fov_x;
+        if !(MIN_ASPECT_RATIO..=MAX_ASPECT_RATIO).contains(&ratio) {
+            return Some(FovProblem::AspectRatio { ratio });
+        }
+        None
+    }
+
+    fn out_of_range(fov_x: f32, fov_y: f32, min_degrees: f32, max_degrees: f32) -> Option<FovProblem> {
+        [fov_x, fov_y]
+            .into_iter()
+            .map(scale_to_degrees)
+            .find(|degrees| !(min_degrees..=max_degrees).contains(degrees))
+            .map(|degrees| FovProblem::OutOfRange { degrees })
+    }
+
+    fn unstable(&self, fov_x: f32, fov_y: f32) -> Option<FovProblem> {
+        let (previous_x, previous_y) = self.previous?;
+        [(previous_x, fov_x), (previous_y, fov_y)]
+            .into_iter()
+            .find(|&(previous, current)| {
+                let change = current / previous;
+                !(1.0 / MAX_FRAME_CHANGE..=MAX_FRAME_CHANGE).contains(&change)
+            })
+            .map(|(previous, current)| FovProblem::Unstable { previous, current })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fov_math::degrees_to_scale;
+
+    /// 16:9 で垂直 `degrees` 度のFOV
+    fn widescreen(degrees: f32) -> (f32, f32) {
+        let vertical = degrees_to_scale(degrees);
+        (vertical * 9.0 / 16.0, vertical)
+    }
+
+    /// 読み取りと書き込みの検査を1フレーム分行う
+    fn frame(guard: &mut FovGuard, base: (f32, f32)) -> GuardVerdict {
+        match guard.check_read(base.0, base.1) {
+            GuardVerdict::Plausible => guard.check_written(base, base),
+            verdict => verdict,
+        }
+    }
+
+    #[test]
+    fn normal_fov_is_plausible() {
+        let mut guard = FovGuard::new();
+        for _ in 0..10 {
+            assert_eq!(frame(&mut guard, widescreen(70.0)), GuardVerdict::Plausible);
+        }
+        assert_eq!(guard.implausible_frames(), 0);
+        assert!(!guard.is_tripped());
+    }
+
+    #[test]
+    fn spyglass_never_enters_safe_mode() {
+        let mut guard = FovGuard::new();
+        assert_eq!(frame(&mut guard, widescreen(70.0)), GuardVerdict::Plausible);
+        // 望遠鏡を構えると1フレームで約 10 倍になる
+        assert!(matches!(
+            frame(&mut guard, widescreen(7.0)),
+            GuardVerdict::Skip(FovProblem::Unstable { .. })
+        ));
+        for _ in 0..SAFE_MODE_THRESHOLD * 2 {
+            assert_eq!(frame(&mut guard, widescreen(7.0)), GuardVerdict::Plausible);
+        }
+        assert!(matches!(
+            frame(&mut guard, widescreen(70.0)),
+            GuardVerdict::Skip(FovProblem::Unstable { .. })
+        ));
+        assert_eq!(frame(&mut guard, widescreen(70.0)), GuardVerdict::Plausible);
+        assert!(!guard.is_tripped());
+    }
+
+    #[test]
+    fn out_of_range_angles_are_skipped_without_strikes() {
+        let mut guard = FovGuard::new();
+        let tiny = widescreen(0.5);
+        for _ in 0..SAFE_MODE_THRESHOLD * 2 {
+            assert!(matches!(
+                guard.check_read(tiny.0, tiny.1),
+                GuardVerdict::Skip(FovProblem::OutOfRange { .. })
+            ));
+        }
+        assert_eq!(guard.implausible_frames(), 0);
+        assert!(!guard.is_tripped());
+    }
+
+    #[test]
+    fn garbage_is_skipped_then_trips_safe_mode() {
+        let mut guard = FovGuard::new();
+        for frame in 1..SAFE_MODE_THRESHOLD {
+            assert!(matches!(
+                guard.check_read(f32::NAN, 1.0),
+                GuardVerdict::Skip(FovProblem::NotFinite { .. })
+            ));
+            assert_eq!(guard.implausible_frames(), frame);
+        }
+        assert!(matches!(
+            guard.check_read(f32::NAN, 1.0),
+            GuardVerdict::SafeMode(FovProblem::NotFinite { .. })
+        ));
+        assert!(guard.is_tripped());
+    }
+
+    #[test]
+    fn each_kind_of_garbage_counts() {
+        for (fov_x, fov_y) in [(f32::INFINITY, 1.0), (0.0, 1.0), (-1.0, -1.0), (1.0, 6.0), (1.0, 0.1)] {
+            let mut guard = FovGuard::new();
+            assert!(matches!(guard.check_read(fov_x, fov_y), GuardVerdict::Skip(_)), "{} {}", fov_x, fov_y);
+            assert_eq!(guard.implausible_frames(), 1, "{} {}", fov_x, fov_y);
+        }
+    }
+
+    #[test]
+    fn write_mismatch_counts_and_a_clean_frame_clears_it() {
+        let mut guard = FovGuard::new();
+        let base = widescreen(70.0);
+        assert_eq!(guard.check_read(base.0, base.1), GuardVerdict::Plausible);
+        assert!(matches!(
+            guard.check_written(base, (base.0, 0.0)),
+            GuardVerdict::Skip(FovProblem::WriteMismatch { .. })
+        ));
+        assert_eq!(guard.implausible_frames(), 1);
+        assert_eq!(frame(&mut guard, base), GuardVerdict::Plausible);
+        assert_eq!(guard.implausible_frames(), 0);
+    }
+
+    #[test]
+    fn reset_forgets_the_previous_frame_and_the_count() {
+        let mut guard = FovGuard::new();
+        assert_eq!(frame(&mut guard, widescreen(70.0)), GuardVerdict::Plausible);
+        guard.check_read(f32::NAN, 1.0);
+        assert_eq!(guard.implausible_frames(), 1);
+
+        guard.reset();
+        assert_eq!(guard.implausible_frames(), 0);
+        // 前フレームを忘れるため、大きく違う値でも見送らない
+        assert_eq!(frame(&mut guard, widescreen(7.0)), GuardVerdict::Plausible);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::fov_patch::FovPair;
+
+/// 投影行列のスケール (1 / tan(fov / 2)) を視野角（度）に変換
+pub fn scale_to_degrees(scale: f32) -> f32 {
+    (2.0 * (1.0 / scale).atan()).to_degrees()
+}
+
+/// 視野角（度）を投影行列のスケールに変換
+pub fn degrees_to_scale(degrees: f32) -> f32 {
+    1.0 / (degrees.to_radians() / 2.0).tan()
+}
+
+/// 視野角に倍率をかけた後の視野角（度）
+///
+/// スケールに倍率 k をかけると視野角は 2 * atan(tan(fov / 2) / k) になる。
+pub fn zoomed_degrees(base_degrees: f32, multiplier: f32) -> f32 {
+    (2.0 * ((base_degrees.to_radians() / 2.0).tan() / multiplier).atan()).to_degrees()
+}
+
+/// 視野角を目標の視野角にするための倍率
+pub fn multiplier_for_target(base_degrees: f32, target_degrees: f32) -> f32 {
+    (base_degrees.to_radians() / 2.0).tan() / (target_degrees.to_radians() / 2.0).tan()
+}
+
+/// 垂直FOVが目標の視野角になる倍率（水平FOVにも同じ倍率をかける）
+pub fn multiplier_for_vertical_target(base: FovPair, target_degrees: f32) -> f32 {
+    multiplier_for_target(scale_to_degrees(base.1), target_degrees)
+}
+
+/// 水平・垂直どちらの視野角も最小視野角を下回らないように倍率を制限する
+///
+/// ズームアウトはしないため、1.0 未満にはならない。
+pub fn clamp_multiplier(base: FovPair, multiplier: f32, min_degrees: f32) -> f32 {
+    let max_multiplier = [base.0, base.1]
+        .into_iter()
+        .map(|scale| multiplier_for_target(scale_to_degrees(scale), min_degrees))
+        .fold(f32::INFINITY, f32::min);
+    multiplier.min(max_multiplier).max(1.0)
+}
+
+/// 元のFOV（スケール）に倍率を適用したFOV（スケール）
+///
+/// 2 * atan(tan(fov / 2) / k) はスケールを k 倍するのと同じで、水平・垂直の比（アスペクト比）を保つ。
+pub fn apply_multiplier(base: FovPair, multiplier: f32) -> FovPair {
+    (base.0 * multiplier, base.1 * multiplier)
+}
+
+/// ズームアニメーションの進み具合 (0.0 = ズームなし, 1.0 = 目標の倍率)
+pub fn zoom_progress(multiplier: f32, full_multiplier: f32) -> f32 {
+    if full_multiplier <= 1.0 {
+        return if multiplier > 1.0 { 1.0 } else { 0.0 };
+    }
+    ((multiplier - 1.0) / (full_multiplier - 1.0)).clamp(0.0, 1.0)
+}
+
+/// 2つのFOV（スケール）を進み具合に応じて補間
+pub fn blend(from: FovPair, to: FovPair, progress: f32) -> FovPair {
+    (
+        from.0 + (to.0 - from.0) * progress,
+        from.1 + (to.1 - from.1) * progress,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+    }
+
+    /// 16:9 で垂直 70 度のFOV（水平 約 102 度）
+    fn widescreen() -> FovPair {
+        let vertical = degrees_to_scale(70.0);
+        (vertical * 9.0 / 16.0, vertical)
+    }
+
+    #[test]
+    fn converts_between_scale_and_degrees() {
+        for degrees in [1.0, 30.0, 70.0, 110.0, 170.0] {
+            assert_close(scale_to_degrees(degrees_to_scale(degrees)), degrees);
+        }
+        assert_close(degrees_to_scale(90.0), 1.0);
+    }
+
+    #[test]
+    fn multiplier_reaches_the_target_angle() {
+        for (base, target) in [(70.0, 10.0), (102.0, 30.0), (90.0, 90.0)] {
+            let multiplier = multiplier_for_target(base, target);
+            assert_close(zoomed_degrees(base, multiplier), target);
+        }
+    }
+
+    #[test]
+    fn vertical_target_zooms_both_axes() {
+        let base = widescreen();
+        let multiplier = multiplier_for_vertical_target(base, 10.0);
+        let zoomed = apply_multiplier(base, multiplier);
+
+        assert_close(scale_to_degrees(zoomed.1), 10.0);
+        assert_close(scale_to_degrees(zoomed.0), zoomed_degrees(scale_to_degrees(base.0), multiplier));
+        assert_close(scale_to_degrees(zoomed.1), zoomed_degrees(scale_to_degrees(base.1), multiplier));
+    }
+
+    #[test]
+    fn clamps_to_the_narrower_axis() {
+        // 横長の画面では垂直FOVが先に最小視野角に達する
+        let base = widescreen();
+        let multiplier = clamp_multiplier(base, 1000.0, 5.0);
+        let zoomed = apply_multiplier(base, multiplier);
+        assert_close(scale_to_degrees(zoomed.1), 5.0);
+        assert!(scale_to_degrees(zoomed.0) > 5.0);
+
+        // 縦長の画面では水平FOVが先に達する
+        let portrait = (base.1, base.0);
+        let multiplier = clamp_multiplier(portrait, 1000.0, 5.0);
+        let zoomed = apply_multiplier(portrait, multiplier);
+        assert_close(scale_to_degrees(zoomed.0), 5.0);
+        assert!(scale_to_degrees(zoomed.1) > 5.0);
+    }
+
+    #[test]
+    fn scale_multiply_is_the_tangent_space_zoom() {
+        let base = widescreen();
+        for multiplier in [1.5, 4.0, 30.0] {
+            let zoomed = apply_multiplier(base, multiplier);
+            // スケールの比は tan(垂直/2) / tan(水平/2) で、画面のアスペクト比に対応する
+            assert_close(zoomed.0 / zoomed.1, base.0 / base.1);
+            // 投影行列のスケールを倍率倍すると、視野角は tan 空間で倍率分狭まる
+            assert_close(scale_to_degrees(zoomed.0), zoomed_degrees(scale_to_degrees(base.0), multiplier));
+            assert_close(scale_to_degrees(zoomed.1), zoomed_degrees(scale_to_degrees(base.1), multiplier));
+        }
+    }
+
+    #[test]
+    fn clamp_never_zooms_out() {
+        let base = widescreen();
+        assert_eq!(clamp_multiplier(base, 0.5, 1.0), 1.0);
+        // 元のFOVがすでに最小視野角より狭い
+        assert_eq!(clamp_multiplier(base, 4.0, 120.0), 1.0);
+        assert_close(clamp_multiplier(base, 4.0, 1.0), 4.0);
+    }
+}
@@ -0,0 +1,123 @@
+/// 水平・垂直FOV（投影行列のスケール）の組
+pub type FovPair = (f32, f32);
+
+/// FOVフィールドへの読み書き
+///
+/// ゲームのメモリ上の LevelRendererPlayer や、メモリを模したバッファを同じように扱うための抽象。
+pub trait FovFields {
+    fn read(&self) -> FovPair;
+    fn write(&mut self, fov: FovPair);
+}
+
+/// 元に戻した結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// 書き込んだ値が残っていたので元の値に戻した
+    Restored,
+    /// 呼び出し中にゲームが値を書き換えたため、その値を残した
+    OverwrittenByGame,
+}
+
+/// renderLevel の呼び出し中だけズーム後のFOVを適用するための記録
+///
+/// 呼び出し前にゲームの元のFOVを記録してズーム後の値を書き込み、呼び出し後に元に戻す。
+/// 毎フレーム元の値から計算し直すため、ゲームがFOVを再計算しなくても倍率が累積せず、
+/// 書き込んだ値はそのフレームの描画にそのまま使われる。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FovPatch {
+    base: FovPair,
+    written: FovPair,
+}
+
+impl FovPatch {
+    /// ゲームが設定した元のFOVを記録し、ズーム後の値を書き込む
+    pub fn apply<F: FovFields>(fields: &mut F, base: FovPair, zoomed: FovPair) -> Self {
+        fields.write(zoomed);
+        Self { base, written: zoomed }
+    }
+
+    /// 記録した元のFOV
+    pub fn base(&self) -> FovPair {
+        self.base
+    }
+
+    /// 書き込んだFOV
+    pub fn written(&self) -> FovPair {
+        self.written
+    }
+
+    /// 元のFOVに戻す（ゲームが書き換えた場合はそちらを優先する）
+    pub fn restore<F: FovFields>(self, fields: &mut F) -> RestoreOutcome {
+        let current = fields.read();
+        if current.0.to_bits() == self.written.0.to_bits() && current.1.to_bits() == self.written.1.to_bits() {
+            fields.write(self.base);
+            RestoreOutcome::Restored
+        } else {
+            RestoreOutcome::OverwrittenByGame
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ゲームのメモリを模したFOVフィールド
+    struct SimulatedFov {
+        fov: FovPair,
+    }
+
+    impl FovFields for SimulatedFov {
+        fn read(&self) -> FovPair {
+            self.fov
+        }
+
+        fn write(&mut self, fov: FovPair) {
+            self.fov = fov;
+        }
+    }
+
+    const BASE: FovPair = (0.8, 1.4);
+
+    fn zoom(fov: FovPair) -> FovPair {
+        (fov.0 * 4.0, fov.1 * 4.0)
+    }
+
+    #[test]
+    fn does_not_compound_when_the_game_keeps_its_value() {
+        let mut fields = SimulatedFov { fov: BASE };
+        for _ in 0..100 {
+            // ゲームは値を再計算せず、前フレームの値がそのまま残る
+            let base = fields.read();
+            assert_eq!(base, BASE);
+            let patch = FovPatch::apply(&mut fields, base, zoom(base));
+            // 描画にはズーム後の値が使われる
+            assert_eq!(fields.read(), zoom(BASE));
+            assert_eq!(patch.restore(&mut fields), RestoreOutcome::Restored);
+        }
+        assert_eq!(fields.read(), BASE);
+    }
+
+    #[test]
+    fn restores_the_base_after_the_call() {
+        let mut fields = SimulatedFov { fov: BASE };
+        let patch = FovPatch::apply(&mut fields, BASE, zoom(BASE));
+        assert_eq!(patch.base(), BASE);
+        assert_eq!(patch.written(), zoom(BASE));
+        assert_eq!(fields.read(), zoom(BASE));
+
+        assert_eq!(patch.restore(&mut fields), RestoreOutcome::Restored);
+        assert_eq!(fields.read(), BASE);
+    }
+
+    #[test]
+    fn keeps_the_value_the_game_wrote_during_the_call() {
+        let mut fields = SimulatedFov { fov: BASE };
+        let patch = FovPatch::apply(&mut fields, BASE, zoom(BASE));
+        // 呼び出し中にゲームがFOVを計算し直した（ダッシュなど）
+        fields.write((0.7, 1.2));
+
+        assert_eq!(patch.restore(&mut fields), RestoreOutcome::OverwrittenByGame);
+        assert_eq!(fields.read(), (0.7, 1.2));
+    }
+}
@@ -0,0 +1,341 @@
+use std::sync::Mutex;
+
+/// ゲームパッドのボタン（値は XInput のビット）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GamepadButton {
+    DpadUp = 0x0001,
+    DpadDown = 0x0002,
+    DpadLeft = 0x0004,
+    DpadRight = 0x0008,
+    Start = 0x0010,
+    Back = 0x0020,
+    LeftThumb = 0x0040,
+    RightThumb = 0x0080,
+    LeftShoulder = 0x0100,
+    RightShoulder = 0x0200,
+    A = 0x1000,
+    B = 0x2000,
+    X = 0x4000,
+    Y = 0x8000,
+}
+
+impl GamepadButton {
+    const ALL: [Self; 14] = [
+        Self::DpadUp,
+        Self::DpadDown,
+        Self::DpadLeft,
+        Self::DpadRight,
+        Self::Start,
+        Self::Back,
+        Self::LeftThumb,
+        Self::RightThumb,
+        Self::LeftShoulder,
+        Self::RightShoulder,
+        Self::A,
+        Self::B,
+        Self::X,
+        Self::Y,
+    ];
+
+    pub fn mask(self) -> u16 {
+        self as u16
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DpadUp => "DPAD_UP",
+            Self::DpadDown => "DPAD_DOWN",
+            Self::DpadLeft => "DPAD_LEFT",
+            Self::DpadRight => "DPAD_RIGHT",
+            Self::Start => "START",
+            Self::Back => "BACK",
+            Self::LeftThumb => "LEFT_THUMB",
+            Self::RightThumb => "RIGHT_THUMB",
+            Self::LeftShoulder => "LEFT_SHOULDER",
+            Self::RightShoulder => "RIGHT_SHOULDER",
+            Self::A => "A",
+            Self::B => "B",
+            Self::X => "X",
+            Self::Y => "Y",
+        }
+    }
+
+    /// 設定のボタン名を変換（大文字・小文字は区別しない）
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.to_uppercase();
+        Self::ALL.into_iter().find(|button| button.as_str() == value)
+    }
+}
+
+/// ゲームパッドのトリガー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GamepadTrigger {
+    Left,
+    Right,
+}
+
+/// ズームに割り当てるゲームパッドの入力
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GamepadInput {
+    Button(GamepadButton),
+    /// トリガー（押し込みの深さを 0.0 ~ 1.0 で読む）
+    Trigger(GamepadTrigger),
+}
+
+impl GamepadInput {
+    /// 設定値を入力に変換（"none" や不明な値は割り当てなし）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_uppercase().as_str() {
+            "LEFT_TRIGGER" | "LT" => Some(Self::Trigger(GamepadTrigger::Left)),
+            "RIGHT_TRIGGER" | "RT" => Some(Self::Trigger(GamepadTrigger::Right)),
+            other => GamepadButton::parse(other).map(Self::Button),
+        }
+    }
+
+    /// 設定値に変換（割り当てなしは "none"）
+    pub fn to_config(input: Option<Self>) -> String {
+        match input {
+            Some(Self::Button(button)) => button.as_str().to_string(),
+            Some(Self::Trigger(GamepadTrigger::Left)) => "LEFT_TRIGGER".to_string(),
+            Some(Self::Trigger(GamepadTrigger::Right)) => "RIGHT_TRIGGER".to_string(),
+            None => "none".to_string(),
+        }
+    }
+}
+
+/// ゲームパッドの状態
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GamepadState {
+    /// 押されているボタンのビット
+    pub buttons: u16,
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+}
+
+impl GamepadState {
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons & button.mask() != 0
+    }
+
+    /// トリガーの押し込みの深さ (0.0 ~ 1.0)
+    pub fn trigger(&self, trigger: GamepadTrigger) -> f32 {
+        let raw = match trigger {
+            GamepadTrigger::Left => self.left_trigger,
+            GamepadTrigger::Right => self.right_trigger,
+        };
+        raw as f32 / u8::MAX as f32
+    }
+}
+
+/// ゲームパッドの状態の読み取り元
+pub trait GamepadSource: Send + Sync {
+    /// 接続されていなければ None
+    fn read(&self) -> Option<GamepadState>;
+}
+
+/// 状態を外から設定できるゲームパッド（テスト用）
+#[derive(Debug, Default)]
+pub struct FakeGamepad {
+    state: Mutex<Option<GamepadState>>,
+}
+
+impl FakeGamepad {
+    pub const fn new() -> Self {
+        Self { state: Mutex::new(None) }
+    }
+
+    pub fn set(&self, state: GamepadState) {
+        *self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(state);
+    }
+
+    pub fn disconnect(&self) {
+        *self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+}
+
+impl GamepadSource for FakeGamepad {
+    fn read(&self) -> Option<GamepadState> {
+        *self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// ゲームパッドの割り当て
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GamepadBindings {
+    /// 押している間ズームする入力
+    pub zoom: Option<GamepadInput>,
+    /// ズーム中に押すたびにズームを強める（スクロール1ノッチと同じ）
+    pub zoom_in: Option<GamepadButton>,
+    /// ズーム中に押すたびにズームを弱める
+    pub zoom_out: Option<GamepadButton>,
+    /// トリガーをこれ以上押し込んだらズームする (0.0 ~ 1.0)
+    pub trigger_threshold: f32,
+}
+
+impl GamepadBindings {
+    /// 何も割り当てられていない（ゲームパッドを読む必要がない）
+    pub fn is_unbound(&self) -> bool {
+        self.zoom.is_none() && self.zoom_in.is_none() && self.zoom_out.is_none()
+    }
+}
+
+/// 1フレームのゲームパッドの入力
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GamepadFrame {
+    /// ズームの入力が押されている
+    pub zoom_held: bool,
+    /// ズームの入力の深さ (0.0 ~ 1.0, ボタンは押していれば 1.0)
+    pub zoom_amount: f32,
+    /// このフレームで押されたズームの強弱ボタン（強めるが正）
+    pub level_steps: i32,
+}
+
+/// ボタンの押下を検出してフレームの入力にする
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GamepadTracker {
+    previous_buttons: u16,
+}
+
+impl GamepadTracker {
+    pub const fn new() -> Self {
+        Self { previous_buttons: 0 }
+    }
+
+    pub fn update(&mut self, state: Option<GamepadState>, bindings: &GamepadBindings) -> GamepadFrame {
+        // 切断されたらすべて離したものとして扱う
+        let state = state.unwrap_or_default();
+        let pressed = state.buttons & !self.previous_buttons;
+        self.previous_buttons = state.buttons;
+
+        let zoom_amount = match bindings.zoom {
+            Some(GamepadInput::Button(button)) if state.is_pressed(button) => 1.0,
+            Some(GamepadInput::Trigger(trigger)) => state.trigger(trigger),
+            _ => 0.0,
+        };
+        let zoom_held = match bindings.zoom {
+            Some(GamepadInput::Trigger(_)) => zoom_amount > 0.0 && zoom_amount >= bindings.trigger_threshold,
+            _ => zoom_amount > 0.0,
+        };
+
+        let was_pressed = |button: Option<GamepadButton>| button.is_some_and(|button| pressed & button.mask() != 0);
+        let level_steps = was_pressed(bindings.zoom_in) as i32 - was_pressed(bindings.zoom_out) as i32;
+
+        GamepadFrame { zoom_held, zoom_amount, level_steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(zoom: Option<GamepadInput>) -> GamepadBindings {
+        GamepadBindings {
+            zoom,
+            zoom_in: Some(GamepadButton::DpadUp),
+            zoom_out: Some(GamepadButton::DpadDown),
+            trigger_threshold: 0.5,
+        }
+    }
+
+    fn buttons(buttons: &[GamepadButton]) -> GamepadState {
+        GamepadState { buttons: buttons.iter().fold(0, |mask, button| mask | button.mask()), ..GamepadState::default() }
+    }
+
+    fn left_trigger(raw: u8) -> GamepadState {
+        GamepadState { left_trigger: raw, ..GamepadState::default() }
+    }
+
+    #[test]
+    fn dpad_steps_only_on_the_press_edge() {
+        let pad = FakeGamepad::new();
+        let bindings = bindings(None);
+        let mut tracker = GamepadTracker::new();
+
+        pad.set(buttons(&[GamepadButton::DpadUp]));
+        assert_eq!(tracker.update(pad.read(), &bindings).level_steps, 1);
+        // 押し続けている間は増えない
+        assert_eq!(tracker.update(pad.read(), &bindings).level_steps, 0);
+
+        pad.set(buttons(&[]));
+        assert_eq!(tracker.update(pad.read(), &bindings).level_steps, 0);
+        pad.set(buttons(&[GamepadButton::DpadDown]));
+        assert_eq!(tracker.update(pad.read(), &bindings).level_steps, -1);
+    }
+
+    #[test]
+    fn dpad_up_and_down_together_cancel() {
+        let pad = FakeGamepad::new();
+        let mut tracker = GamepadTracker::new();
+        pad.set(buttons(&[GamepadButton::DpadUp, GamepadButton::DpadDown]));
+        assert_eq!(tracker.update(pad.read(), &bindings(None)).level_steps, 0);
+    }
+
+    #[test]
+    fn trigger_holds_zoom_from_the_threshold() {
+        let pad = FakeGamepad::new();
+        let bindings = bindings(Some(GamepadInput::Trigger(GamepadTrigger::Left)));
+        let mut tracker = GamepadTracker::new();
+
+        pad.set(left_trigger(100));
+        let frame = tracker.update(pad.read(), &bindings);
+        assert!(!frame.zoom_held);
+        assert!((frame.zoom_amount - 100.0 / 255.0).abs() < 1e-6);
+
+        pad.set(left_trigger(128));
+        assert!(tracker.update(pad.read(), &bindings).zoom_held);
+        pad.set(left_trigger(255));
+        let frame = tracker.update(pad.read(), &bindings);
+        assert!(frame.zoom_held);
+        assert_eq!(frame.zoom_amount, 1.0);
+    }
+
+    #[test]
+    fn released_trigger_never_holds_even_with_zero_threshold() {
+        let mut bindings = bindings(Some(GamepadInput::Trigger(GamepadTrigger::Right)));
+        bindings.trigger_threshold = 0.0;
+        let mut tracker = GamepadTracker::new();
+        assert!(!tracker.update(Some(GamepadState::default()), &bindings).zoom_held);
+    }
+
+    #[test]
+    fn zoom_button_holds_at_full_amount() {
+        let pad = FakeGamepad::new();
+        let bindings = bindings(Some(GamepadInput::Button(GamepadButton::LeftShoulder)));
+        let mut tracker = GamepadTracker::new();
+
+        pad.set(buttons(&[GamepadButton::LeftShoulder]));
+        let frame = tracker.update(pad.read(), &bindings);
+        assert!(frame.zoom_held);
+        assert_eq!(frame.zoom_amount, 1.0);
+    }
+
+    #[test]
+    fn disconnect_releases_everything() {
+        let pad = FakeGamepad::new();
+        let bindings = bindings(Some(GamepadInput::Button(GamepadButton::A)));
+        let mut tracker = GamepadTracker::new();
+
+        pad.set(buttons(&[GamepadButton::A, GamepadButton::DpadUp]));
+        assert!(tracker.update(pad.read(), &bindings).zoom_held);
+
+        pad.disconnect();
+        assert_eq!(pad.read(), None);
+        assert_eq!(tracker.update(pad.read(), &bindings), GamepadFrame::default());
+
+        // 再接続したときに押したままのボタンは新しい押下として扱う
+        pad.set(buttons(&[GamepadButton::DpadUp]));
+        assert_eq!(tracker.update(pad.read(), &bindings).level_steps, 1);
+    }
+
+    #[test]
+    fn input_names_round_trip() {
+        for button in GamepadButton::ALL {
+            let input = Some(GamepadInput::Button(button));
+            assert_eq!(GamepadInput::parse(&GamepadInput::to_config(input)), input);
+        }
+        assert_eq!(GamepadInput::parse("lt"), Some(GamepadInput::Trigger(GamepadTrigger::Left)));
+        assert_eq!(GamepadInput::parse("right_trigger"), Some(GamepadInput::Trigger(GamepadTrigger::Right)));
+        assert_eq!(GamepadInput::parse("none"), None);
+        assert_eq!(GamepadInput::to_config(None), "none");
+    }
+}
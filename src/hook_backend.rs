@@ -0,0 +1,395 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::lifecycle::StartupError;
+use crate::logger;
+#[cfg(target_os = "windows")]
+use crate::minhook_backend::MinHookBackend;
+use crate::prologue::{self, ForeignDetourPolicy};
+
+/// 実行環境で使用するフックのバックエンド（テストではモックを直接使う）
+#[cfg(target_os = "windows")]
+pub type PlatformHookBackend = MinHookBackend;
+
+/// Mod全体で使用するフックの管理
+#[cfg(target_os = "windows")]
+pub static HOOKS: HookManager<PlatformHookBackend> = HookManager::new(PlatformHookBackend::new());
+
+/// フック操作のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookError {
+    /// バックエンドは初期化済み
+    AlreadyInitialized,
+    /// バックエンドが初期化されていない
+    NotInitialized,
+    /// 対象にはすでにフックが作成されている
+    AlreadyCreated,
+    /// 対象にフックが作成されていない
+    NotCreated,
+    /// フックはすでに有効
+    AlreadyEnabled,
+    /// フックはすでに無効
+    AlreadyDisabled,
+    /// 対象が実行可能なメモリではない
+    NotExecutable,
+    /// 対象の関数をフックできない
+    UnsupportedFunction,
+    /// トランポリン用のメモリを確保できない
+    MemoryAlloc,
+    /// メモリ保護を変更できない
+    MemoryProtect,
+    /// バックエンド固有の不明なエラー
+    Unknown(i32),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyInitialized => write!(f, "hook backend is already initialized"),
+            Self::NotInitialized => write!(f, "hook backend is not initialized"),
+            Self::AlreadyCreated => write!(f, "hook is already created"),
+            Self::NotCreated => write!(f, "hook is not created"),
+            Self::AlreadyEnabled => write!(f, "hook is already enabled"),
+            Self::AlreadyDisabled => write!(f, "hook is already disabled"),
+            Self::NotExecutable => write!(f, "target is not executable"),
+            Self::UnsupportedFunction => write!(f, "target function cannot be hooked"),
+            Self::MemoryAlloc => write!(f, "failed to allocate trampoline memory"),
+            Self::MemoryProtect => write!(f, "failed to change memory protection"),
+            Self::Unknown(code) => write!(f, "unknown hook error ({})", code),
+        }
+    }
+}
+
+/// 関数フックの実装
+///
+/// アドレスは関数ポインタを usize にしたもの。
+pub trait HookBackend: Send + Sync {
+    fn initialize(&self) -> Result<(), HookError>;
+
+    fn uninitialize(&self) -> Result<(), HookError>;
+
+    /// フックを作成し、元の関数を呼び出すためのアドレスを返す（まだ有効化はしない）
+    ///
+    /// # Safety
+    /// `target` と `detour` が同じシグネチャの関数を指していること。
+    unsafe fn create(&self, target: usize, detour: usize) -> Result<usize, HookError>;
+
+    /// # Safety
+    /// `target` が `create` で作成したフックの対象であること。
+    unsafe fn enable(&self, target: usize) -> Result<(), HookError>;
+
+    /// # Safety
+    /// `target` が `create` で作成したフックの対象であること。
+    unsafe fn disable(&self, target: usize) -> Result<(), HookError>;
+
+    /// # Safety
+    /// `target` が `create` で作成したフックの対象であり、削除後にトランポリンが呼ばれないこと。
+    unsafe fn remove(&self, target: usize) -> Result<(), HookError>;
+}
+
+/// 管理しているフック
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstalledHook {
+    pub name: &'static str,
+    pub target: usize,
+    /// 元の関数を呼び出すためのアドレス
+    pub trampoline: usize,
+    pub enabled: bool,
+}
+
+struct ManagerState {
+    attached: bool,
+    hooks: Vec<InstalledHook>,
+}
+
+/// バックエンドの初期化からフックの作成・削除までを管理する
+pub struct HookManager<B: HookBackend> {
+    backend: B,
+    state: Mutex<ManagerState>,
+}
+
+impl<B: HookBackend> HookManager<B> {
+    pub const fn new(backend: B) -> Self {
+        Self {
+            backend,
+            state: Mutex::new(ManagerState { attached: false, hooks: Vec::new() }),
+        }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, ManagerState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// バックエンドを初期化（初期化済みなら何もしない）
+    pub fn attach(&self) -> Result<(), HookError> {
+        let mut state = self.state();
+        if state.attached {
+            return Ok(());
+        }
+        self.backend.initialize()?;
+        state.attached = true;
+        Ok(())
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.state().attached
+    }
+
+    /// 管理しているフックの一覧
+    pub fn installed(&self) -> Vec<InstalledHook> {
+        self.state().hooks.clone()
+    }
+
+    /// フックを無効な状態で作成し、元の関数を呼び出すためのアドレスを返す
+    ///
+    /// デトゥアが呼ばれる前にアドレスを保存できるよう、有効化は `enable` で別に行う。
+    ///
+    /// # Safety
+    /// `target` と `detour` が同じシグネチャの関数を指していること。
+    pub unsafe fn create(&self, name: &'static str, target: usize, detour: usize) -> Result<usize, HookError> {
+        let mut state = self.state();
+        if !state.attached {
+            return Err(HookError::NotInitialized);
+        }
+
+        let trampoline = self.backend.create(target, detour)?;
+        state.hooks.push(InstalledHook { name, target, trampoline, enabled: false });
+        Ok(trampoline)
+    }
+
+    /// 関数の先頭に他のModのデトゥアがないか確認してからフックを作成する（有効化はしない）
+    ///
+    /// デトゥアが見つかった場合、`policy` が Abort ならフックを作成せずにエラーを返す。
+    ///
+    /// # Safety
+    /// `image` が `image_base` に読み込まれたイメージであり、`rva` の関数と `detour` が同じシグネチャであること。
+    pub unsafe fn create_checked(
+        &self,
+        name: &'static str,
+        image: &[u8],
+        image_base: usize,
+        rva: usize,
+        detour: usize,
+        policy: ForeignDetourPolicy,
+    ) -> Result<usize, StartupError> {
+        if let Some(foreign) = prologue::detect_detour(image, image_base, rva) {
+            match policy {
+                ForeignDetourPolicy::Chain => logger::warn(&format!(
+                    "{}: prologue is already detoured by another mod ({}); hooking on top of it",
+                    name, foreign
+                )),
+                ForeignDetourPolicy::Abort => return Err(StartupError::ForeignDetour { name, detour: foreign }),
+            }
+        }
+        self.create(name, image_base + rva, detour)
+            .map_err(|error| StartupError::Install { name, error })
+    }
+
+    /// 作成済みのフックを有効化（有効なら何もしない）
+    ///
+    /// # Safety
+    /// `create` で返したアドレスをデトゥアから使えるようになっていること。
+    pub unsafe fn enable(&self, target: usize) -> Result<(), HookError> {
+        let mut state = self.state();
+        let hook = state
+            .hooks
+            .iter_mut()
+            .find(|hook| hook.target == target)
+            .ok_or(HookError::NotCreated)?;
+        if hook.enabled {
+            return Ok(());
+        }
+        self.backend.enable(target)?;
+        hook.enabled = true;
+        Ok(())
+    }
+
+    /// フックを無効化（作成したままにし、トランポリンは引き続き使用できる）
+    ///
+    /// # Safety
+    /// 他のスレッドが対象の関数を実行中でも安全に書き換えられるバックエンドであること。
+    pub unsafe fn disable(&self, target: usize) -> Result<(), HookError> {
+        let mut state = self.state();
+        let hook = state
+            .hooks
+            .iter_mut()
+            .find(|hook| hook.target == target)
+            .ok_or(HookError::NotCreated)?;
+        if !hook.enabled {
+            return Ok(());
+        }
+        self.backend.disable(target)?;
+        hook.enabled = false;
+        Ok(())
+    }
+
+    /// すべてのフックを無効化・削除し、バックエンドを終了する
+    ///
+    /// 途中で失敗しても残りの処理は続け、最初のエラーを返す。
+    ///
+    /// # Safety
+    /// 以後トランポリンが呼ばれないこと。
+    pub unsafe fn detach(&self) -> Result<(), HookError> {
+        let mut state = self.state();
+        if !state.attached {
+            return Ok(());
+        }
+
+        let mut first_error = None;
+        for hook in std::mem::take(&mut state.hooks) {
+            if hook.enabled {
+                if let Err(error) = self.backend.disable(hook.target) {
+                    first_error.get_or_insert(error);
+                }
+            }
+            if let Err(error) = self.backend.remove(hook.target) {
+                first_error.get_or_insert(error);
+            }
+        }
+        if let Err(error) = self.backend.uninitialize() {
+            first_error.get_or_insert(error);
+        }
+        state.attached = false;
+
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_hook_backend::MockHookBackend;
+
+    const TARGET: usize = 0x1000;
+    const DETOUR: usize = 0x2000;
+
+    fn attached() -> HookManager<MockHookBackend> {
+        let manager = HookManager::new(MockHookBackend::new());
+        manager.attach().unwrap();
+        manager
+    }
+
+    #[test]
+    fn attach_is_idempotent() {
+        let manager = HookManager::new(MockHookBackend::new());
+        assert!(!manager.is_attached());
+        manager.attach().unwrap();
+        manager.attach().unwrap();
+        assert!(manager.is_attached());
+        assert!(manager.backend().is_initialized());
+    }
+
+    #[test]
+    fn creates_disabled_and_enables() {
+        let manager = attached();
+        let trampoline = unsafe { manager.create("test", TARGET, DETOUR) }.unwrap();
+        assert_eq!(manager.backend().is_enabled(TARGET), Some(false));
+        assert_eq!(manager.backend().detour_of(TARGET), Some(DETOUR));
+        assert_eq!(
+            manager.installed(),
+            [InstalledHook { name: "test", target: TARGET, trampoline, enabled: false }]
+        );
+
+        unsafe { manager.enable(TARGET) }.unwrap();
+        // 有効なフックの再有効化は何もしない
+        unsafe { manager.enable(TARGET) }.unwrap();
+        assert_eq!(manager.backend().is_enabled(TARGET), Some(true));
+        assert!(manager.installed()[0].enabled);
+
+        unsafe { manager.disable(TARGET) }.unwrap();
+        unsafe { manager.disable(TARGET) }.unwrap();
+        assert_eq!(manager.backend().is_enabled(TARGET), Some(false));
+    }
+
+    #[test]
+    fn requires_attach_before_create() {
+        let manager = HookManager::new(MockHookBackend::new());
+        assert_eq!(unsafe { manager.create("test", TARGET, DETOUR) }, Err(HookError::NotInitialized));
+        assert!(manager.installed().is_empty());
+    }
+
+    #[test]
+    fn reports_backend_failures() {
+        let manager = attached();
+        manager.backend().fail_next_create(HookError::UnsupportedFunction);
+        assert_eq!(unsafe { manager.create("test", TARGET, DETOUR) }, Err(HookError::UnsupportedFunction));
+        assert!(manager.installed().is_empty());
+
+        unsafe { manager.create("test", TARGET, DETOUR) }.unwrap();
+        assert_eq!(unsafe { manager.create("test", TARGET, DETOUR) }, Err(HookError::AlreadyCreated));
+
+        manager.backend().fail_next_enable(HookError::MemoryProtect);
+        assert_eq!(unsafe { manager.enable(TARGET) }, Err(HookError::MemoryProtect));
+        assert!(!manager.installed()[0].enabled);
+        assert_eq!(manager.backend().is_enabled(TARGET), Some(false));
+
+        assert_eq!(unsafe { manager.enable(0x3000) }, Err(HookError::NotCreated));
+        assert_eq!(unsafe { manager.disable(0x3000) }, Err(HookError::NotCreated));
+    }
+
+    /// 0x20 の関数の先頭に `prologue` を置いたイメージ
+    fn image_with_prologue(prologue: &[u8]) -> Vec<u8> {
+        let mut image = vec![0xCC; 0x100];
+        image[0x20..0x20 + prologue.len()].copy_from_slice(prologue);
+        image
+    }
+
+    #[test]
+    fn abort_policy_stops_before_create() {
+        let manager = attached();
+        let image = image_with_prologue(&[0xE9, 0x00, 0x10, 0x00, 0x00]);
+        let result = unsafe { manager.create_checked("test", &image, TARGET, 0x20, DETOUR, ForeignDetourPolicy::Abort) };
+        assert!(matches!(result, Err(StartupError::ForeignDetour { name: "test", .. })));
+        assert!(manager.installed().is_empty());
+        assert_eq!(manager.backend().hook_count(), 0);
+    }
+
+    #[test]
+    fn chain_policy_hooks_on_top_of_a_detour() {
+        let manager = attached();
+        let image = image_with_prologue(&[0xE9, 0x00, 0x10, 0x00, 0x00]);
+        unsafe { manager.create_checked("test", &image, TARGET, 0x20, DETOUR, ForeignDetourPolicy::Chain) }.unwrap();
+        assert_eq!(manager.installed()[0].target, TARGET + 0x20);
+        assert_eq!(manager.backend().detour_of(TARGET + 0x20), Some(DETOUR));
+    }
+
+    #[test]
+    fn clean_prologue_is_hooked_with_either_policy() {
+        let manager = attached();
+        let image = image_with_prologue(&[0x48, 0x89, 0x5C, 0x24, 0x08]);
+        unsafe { manager.create_checked("test", &image, TARGET, 0x20, DETOUR, ForeignDetourPolicy::Abort) }.unwrap();
+        assert_eq!(manager.backend().hook_count(), 1);
+
+        manager.backend().fail_next_create(HookError::UnsupportedFunction);
+        let result = unsafe { manager.create_checked("other", &image, 0x9000, 0x20, DETOUR, ForeignDetourPolicy::Abort) };
+        assert_eq!(result, Err(StartupError::Install { name: "other", error: HookError::UnsupportedFunction }));
+    }
+
+    #[test]
+    fn detach_removes_every_hook() {
+        let manager = attached();
+        unsafe {
+            manager.create("enabled", TARGET, DETOUR).unwrap();
+            manager.enable(TARGET).unwrap();
+            manager.create("disabled", 0x3000, DETOUR).unwrap();
+            manager.detach().unwrap();
+        }
+        assert!(!manager.is_attached());
+        assert!(manager.installed().is_empty());
+        assert!(!manager.backend().is_initialized());
+        assert_eq!(manager.backend().hook_count(), 0);
+
+        // 終了後は何もしない
+        unsafe { manager.detach() }.unwrap();
+        // 再び開始できる
+        manager.attach().unwrap();
+        assert!(manager.backend().is_initialized());
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::logger;
+use crate::paths;
+use crate::hook_target::{HookTarget, ResolvedTarget};
+
+/// FNV-1a (64bit) のパラメータ
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// PE ヘッダ内の各フィールドの位置
+const DOS_E_LFANEW: usize = 0x3C;
+const PE_SIGNATURE: u32 = 0x0000_4550; // "PE\0\0"
+const FILE_HEADER_SIZE: usize = 20;
+const SECTION_HEADER_SIZE: usize = 40;
+
+/// hook_cache.yml 用の構造体
+#[derive(Serialize, Deserialize, Default)]
+struct HookCacheYaml {
+    /// イメージのフィンガープリント (16進数)
+    fingerprint: String,
+    /// フック対象名 → シグネチャが一致した位置の RVA
+    #[serde(default)]
+    targets: BTreeMap<String, u64>,
+    /// フック対象名 → 解決した関数の RVA
+    #[serde(default)]
+    functions: BTreeMap<String, u64>,
+}
+
+/// フック対象アドレスのキャッシュ
+///
+/// ゲームの実行ファイルが変わらない限り、前回解決した RVA を再利用してフルスキャンを省略する。
+pub struct HookCache {
+    /// None の場合はフィンガープリントを計算できなかったためキャッシュを使用しない
+    fingerprint: Option<u64>,
+    targets: BTreeMap<String, u64>,
+    functions: BTreeMap<String, u64>,
+    dirty: bool,
+}
+
+impl HookCache {
+    /// キャッシュファイルのパスを取得 (mods/Zoom/hook_cache.yml)
+    pub fn cache_path() -> PathBuf {
+        paths::mod_file("hook_cache.yml")
+    }
+
+    /// イメージに対応するキャッシュを読み込む（フィンガープリントが異なれば空のキャッシュ）
+    pub fn load(image: &[u8]) -> Self {
+        let content = fs::read_to_string(Self::cache_path()).ok();
+        Self::from_stored(image, content.as_deref())
+    }
+
+    /// 保存されていた内容からキャッシュを作る（内容が壊れているか別のイメージのものなら使わない）
+    fn from_stored(image: &[u8], content: Option<&str>) -> Self {
+        let fingerprint = image_fingerprint(image);
+        let mut cache = Self {
+            fingerprint,
+            targets: BTreeMap::new(),
+            functions: BTreeMap::new(),
+            dirty: false,
+        };
+
+        let Some(fingerprint) = fingerprint else {
+            logger::warn("could not fingerprint the game image, hook cache disabled");
+            return cache;
+        };
+
+        let stored = content.and_then(|content| serde_yaml::from_str::<HookCacheYaml>(content).ok());
+
+        match stored {
+            Some(yaml) if yaml.fingerprint == format_fingerprint(fingerprint) => {
+                cache.targets = yaml.targets;
+                cache.functions = yaml.functions;
+            }
+            Some(_) => {
+                logger::info("game image changed since the last launch, hook cache invalidated");
+                cache.dirty = true;
+            }
+            None => cache.dirty = true,
+        }
+        cache
+    }
+
+    /// 前回解決した関数の RVA（検証前）
+    pub fn cached_function_rva(&self, name: &str) -> Option<usize> {
+        self.functions.get(name).map(|&rva| rva as usize)
+    }
+
+    /// フック対象の位置を解決する
+    ///
+    /// キャッシュ済みの一致位置がまだいずれかのシグネチャに一致すればそこから解決し、
+    /// 一致しない場合のみイメージ全体をスキャンしてキャッシュを更新する。
+    pub fn resolve(&mut self, target: &HookTarget, image: &[u8], image_base: usize) -> Option<ResolvedTarget> {
+        if let Some(&rva) = self.targets.get(target.name) {
+            if let Some(resolved) = target.resolve_at(image, image_base, rva as usize) {
+                logger::info(&format!("{}: cached RVA 0x{:X} verified", target.name, rva));
+                self.remember_function(target.name, resolved.rva);
+                return Some(resolved);
+            }
+            logger::warn(&format!(
+                "{}: cached RVA 0x{:X} no longer matches, rescanning",
+                target.name, rva
+            ));
+            self.targets.remove(target.name);
+            self.dirty = true;
+        }
+
+        let resolved = target.scan(image, image_base)?;
+        if self.fingerprint.is_some() {
+            self.targets.insert(target.name.to_string(), resolved.match_rva as u64);
+            self.remember_function(target.name, resolved.rva);
+            self.dirty = true;
+        }
+        Some(resolved)
+    }
+
+    fn remember_function(&mut self, name: &str, rva: usize) {
+        if self.functions.get(name) != Some(&(rva as u64)) {
+            self.functions.insert(name.to_string(), rva as u64);
+            self.dirty = true;
+        }
+    }
+
+    /// 変更があればキャッシュファイルに保存
+    pub fn save(&mut self) {
+        let Some(fingerprint) = self.fingerprint else {
+            return;
+        };
+        if !self.dirty {
+            return;
+        }
+
+        let yaml = HookCacheYaml {
+            fingerprint: format_fingerprint(fingerprint),
+            targets: self.targets.clone(),
+            functions: self.functions.clone(),
+        };
+        let written = serde_yaml::to_string(&yaml)
+            .ok()
+            .is_some_and(|content| fs::write(Self::cache_path(), content).is_ok());
+        if written {
+            self.dirty = false;
+        } else {
+            logger::warn("failed to write hook cache");
+        }
+    }
+}
+
+fn format_fingerprint(fingerprint: u64) -> String {
+    format!("{:016X}", fingerprint)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// PE ヘッダとセクションヘッダからイメージのフィンガープリントを計算
+///
+/// ローダーが書き換える可能性のある ImageBase などは含めず、
+/// ビルドごとに変わるタイムスタンプ・チェックサム・セクション配置のみをハッシュする。
+pub fn image_fingerprint(image: &[u8]) -> Option<u64> {
+    let nt = read_u32(image, DOS_E_LFANEW)? as usize;
+    if read_u32(image, nt)? != PE_SIGNATURE {
+        return None;
+    }
+
+    let file_header = nt + 4;
+    let section_count = read_u16(image, file_header + 2)? as usize;
+    let optional_header_size = read_u16(image, file_header + 16)? as usize;
+    let optional_header = file_header + FILE_HEADER_SIZE;
+
+    let mut hash = FNV_OFFSET_BASIS;
+    // TimeDateStamp
+    hash = fnv1a(hash, image.get(file_header + 4..file_header + 8)?);
+    // AddressOfEntryPoint
+    hash = fnv1a(hash, image.get(optional_header + 16..optional_header + 20)?);
+    // SizeOfImage, SizeOfHeaders, CheckSum
+    hash = fnv1a(hash, image.get(optional_header + 56..optional_header + 68)?);
+
+    let sections = optional_header + optional_header_size;
+    for index in 0..section_count {
+        let section = sections + index * SECTION_HEADER_SIZE;
+        // Name, VirtualSize, VirtualAddress, SizeOfRawData, PointerToRawData
+        hash = fnv1a(hash, image.get(section..section + 24)?);
+    }
+
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Signature;
+
+    const IMAGE_BASE: usize = 0x1_4000_0000;
+    const NT_HEADERS: usize = 0x80;
+    const SECTIONS: usize = NT_HEADERS + 4 + FILE_HEADER_SIZE + 0xF0;
+    const FUNCTION_RVA: usize = 0x300;
+    const PROLOGUE: [u8; 5] = [0x48, 0x89, 0x5C, 0x24, 0x08];
+
+    /// PE ヘッダとセクションを1つ持つ 0x400 バイトのイメージ（0x300 に関数の先頭を置く）
+    fn image(timestamp: u32) -> Vec<u8> {
+        let mut image = vec![0xCC; 0x400];
+        image[DOS_E_LFANEW..DOS_E_LFANEW + 4].copy_from_slice(&(NT_HEADERS as u32).to_le_bytes());
+        image[NT_HEADERS..NT_HEADERS + 4].copy_from_slice(&PE_SIGNATURE.to_le_bytes());
+        let file_header = NT_HEADERS + 4;
+        image[file_header + 2..file_header + 4].copy_from_slice(&1u16.to_le_bytes());
+        image[file_header + 4..file_header + 8].copy_from_slice(&timestamp.to_le_bytes());
+        image[file_header + 16..file_header + 18].copy_from_slice(&0xF0u16.to_le_bytes());
+        image[SECTIONS..SECTIONS + 8].copy_from_slice(b".text\0\0\0");
+        image[FUNCTION_RVA..FUNCTION_RVA + PROLOGUE.len()].copy_from_slice(&PROLOGUE);
+        image
+    }
+
+    fn target() -> HookTarget {
+        HookTarget::new("render_level", vec![Signature::parse("48 89 5C 24 08").unwrap()])
+    }
+
+    fn stored(image: &[u8], rva: usize) -> String {
+        let yaml = HookCacheYaml {
+            fingerprint: format_fingerprint(image_fingerprint(image).unwrap()),
+            targets: BTreeMap::from([("render_level".to_string(), rva as u64)]),
+            functions: BTreeMap::from([("render_level".to_string(), rva as u64)]),
+        };
+        serde_yaml::to_string(&yaml).unwrap()
+    }
+
+    #[test]
+    fn fingerprint_changes_with_the_pe_header() {
+        let first = image_fingerprint(&image(1)).unwrap();
+        assert_eq!(image_fingerprint(&image(1)), Some(first));
+        assert_ne!(image_fingerprint(&image(2)), Some(first));
+
+        let mut renamed = image(1);
+        renamed[SECTIONS] = b'_';
+        assert_ne!(image_fingerprint(&renamed), Some(first));
+    }
+
+    #[test]
+    fn fingerprint_ignores_code_but_rejects_non_pe() {
+        let mut patched = image(1);
+        patched[FUNCTION_RVA] = 0xE9;
+        assert_eq!(image_fingerprint(&patched), image_fingerprint(&image(1)));
+
+        let mut broken = image(1);
+        broken[NT_HEADERS] = 0;
+        assert_eq!(image_fingerprint(&broken), None);
+        assert_eq!(image_fingerprint(&[0; 0x10]), None);
+    }
+
+    #[test]
+    fn matching_cache_entry_is_used() {
+        let image = image(1);
+        let mut cache = HookCache::from_stored(&image, Some(&stored(&image, FUNCTION_RVA)));
+        assert_eq!(cache.cached_function_rva("render_level"), Some(FUNCTION_RVA));
+        let resolved = cache.resolve(&target(), &image, IMAGE_BASE).unwrap();
+        assert_eq!(resolved.rva, FUNCTION_RVA);
+        assert!(!cache.dirty);
+    }
+
+    #[test]
+    fn cache_from_another_image_is_ignored() {
+        let old_image = image(1);
+        let image = image(2);
+        // 前のビルドではシグネチャに一致しない位置を指していた
+        let cache = HookCache::from_stored(&image, Some(&stored(&old_image, 0x200)));
+        assert_eq!(cache.cached_function_rva("render_level"), None);
+        assert!(cache.dirty);
+    }
+
+    #[test]
+    fn stale_cache_entry_is_rescanned() {
+        let image = image(1);
+        let mut cache = HookCache::from_stored(&image, Some(&stored(&image, 0x200)));
+        let resolved = cache.resolve(&target(), &image, IMAGE_BASE).unwrap();
+        assert_eq!(resolved.rva, FUNCTION_RVA);
+        assert_eq!(cache.targets.get("render_level"), Some(&(FUNCTION_RVA as u64)));
+        assert_eq!(cache.cached_function_rva("render_level"), Some(FUNCTION_RVA));
+        assert!(cache.dirty);
+    }
+
+    #[test]
+    fn unreadable_cache_is_ignored() {
+        let image = image(1);
+        let cache = HookCache::from_stored(&image, Some("not: [valid"));
+        assert_eq!(cache.cached_function_rva("render_level"), None);
+        let cache = HookCache::from_stored(&image, None);
+        assert_eq!(cache.cached_function_rva("render_level"), None);
+    }
+}
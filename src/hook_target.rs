@@ -0,0 +1,168 @@
+use crate::logger;
+use crate::signature::Signature;
+use crate::version;
+
+/// フック対象の関数と、その位置を特定するためのシグネチャ候補
+///
+/// シグネチャは先頭から順に試し、最初に一致したものを採用する。
+#[derive(Clone, Debug)]
+pub struct HookTarget {
+    /// キャッシュやログで使う名前 (例: "render_level")
+    pub name: &'static str,
+    pub signatures: Vec<Signature>,
+}
+
+/// シグネチャによって特定されたフック対象の位置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResolvedTarget {
+    /// 一致したシグネチャの番号 (0始まり)
+    pub signature_index: usize,
+    /// シグネチャが一致した位置の RVA
+    pub match_rva: usize,
+    /// 解決ステップ適用後の関数の RVA
+    pub rva: usize,
+}
+
+impl HookTarget {
+    pub fn new(name: &'static str, signatures: Vec<Signature>) -> Self {
+        Self { name, signatures }
+    }
+
+    /// 指定位置で一致するシグネチャがあれば解決する（キャッシュの検証用）
+    pub fn resolve_at(&self, image: &[u8], image_base: usize, match_rva: usize) -> Option<ResolvedTarget> {
+        self.signatures.iter().enumerate().find_map(|(signature_index, signature)| {
+            if !signature.matches_at(image, match_rva) {
+                return None;
+            }
+            let rva = signature.resolve_at(image, image_base, match_rva)?;
+            Some(ResolvedTarget { signature_index, match_rva, rva })
+        })
+    }
+
+    /// シグネチャを順に試してイメージ全体を検索する
+    pub fn scan(&self, image: &[u8], image_base: usize) -> Option<ResolvedTarget> {
+        self.signatures.iter().enumerate().find_map(|(signature_index, signature)| {
+            let Some(match_rva) = signature.find(image) else {
+                logger::info(&format!("{}: signature #{} not found", self.name, signature_index + 1));
+                return None;
+            };
+            match signature.resolve_at(image, image_base, match_rva) {
+                Some(rva) => Some(ResolvedTarget { signature_index, match_rva, rva }),
+                None => {
+                    logger::warn(&format!(
+                        "{}: signature #{} matched at RVA 0x{:X} but could not be resolved",
+                        self.name,
+                        signature_index + 1,
+                        match_rva
+                    ));
+                    None
+                }
+            }
+        })
+    }
+
+    /// 解決結果をログに記録する
+    pub fn report_resolved(&self, resolved: &ResolvedTarget) {
+        logger::info(&format!(
+            "{}: signature #{} of {} matched at RVA 0x{:X}, target RVA 0x{:X}",
+            self.name,
+            resolved.signature_index + 1,
+            self.signatures.len(),
+            resolved.match_rva,
+            resolved.rva
+        ));
+    }
+
+    /// どのシグネチャも一致しなかったことをログに記録する
+    pub fn report_failure(&self) {
+        let game_version = version::game_version()
+            .map(|version| version.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        logger::error("================================================================");
+        for line in self.failure_report(&game_version) {
+            logger::error(&line);
+        }
+        logger::error("================================================================");
+    }
+
+    /// 失敗の報告の各行（試したシグネチャをすべて含む）
+    fn failure_report(&self, game_version: &str) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{}: none of the {} signatures matched (game version {}), the hook is NOT installed",
+            self.name,
+            self.signatures.len(),
+            game_version
+        )];
+        for (index, signature) in self.signatures.iter().enumerate() {
+            lines.push(format!("  #{}: {}", index + 1, signature));
+        }
+        lines.push("this game version is probably not supported yet; see offsets.yml in the README".to_string());
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMAGE_BASE: usize = 0x1_4000_0000;
+
+    /// 0x100 バイトの空のイメージに命令を配置する
+    fn image_with(offset: usize, bytes: &[u8]) -> Vec<u8> {
+        let mut image = vec![0xCC; 0x100];
+        image[offset..offset + bytes.len()].copy_from_slice(bytes);
+        image
+    }
+
+    fn target(signatures: &[&str]) -> HookTarget {
+        HookTarget::new("render_level", signatures.iter().map(|text| Signature::parse(text).unwrap()).collect())
+    }
+
+    #[test]
+    fn falls_back_to_the_next_signature() {
+        // 0x40: call 0x80 (次の命令 0x45 からの相対 +0x3B)
+        let image = image_with(0x40, &[0xE8, 0x3B, 0x00, 0x00, 0x00, 0x48, 0x8B, 0xD8]);
+        let target = target(&["48 89 5C 24 08 57", "E8 ?? ?? ?? ?? 48 8B D8 | offset 1, rel32"]);
+
+        let resolved = target.scan(&image, IMAGE_BASE).unwrap();
+        assert_eq!(resolved, ResolvedTarget { signature_index: 1, match_rva: 0x40, rva: 0x80 });
+        assert_eq!(target.resolve_at(&image, IMAGE_BASE, 0x40), Some(resolved));
+    }
+
+    #[test]
+    fn falls_back_when_a_match_cannot_be_resolved() {
+        // 最初のシグネチャは一致するが呼び出し先がイメージの外
+        let mut image = image_with(0x10, &[0xE8, 0x00, 0x10, 0x00, 0x00, 0x48, 0x8B, 0xD8]);
+        image[0x60..0x65].copy_from_slice(&[0x48, 0x89, 0x5C, 0x24, 0x08]);
+        let target = target(&["E8 ?? ?? ?? ?? 48 8B D8 | offset 1, rel32", "48 89 5C 24 08"]);
+
+        let resolved = target.scan(&image, IMAGE_BASE).unwrap();
+        assert_eq!(resolved.signature_index, 1);
+        assert_eq!(resolved.rva, 0x60);
+    }
+
+    #[test]
+    fn first_matching_signature_wins() {
+        let image = image_with(0x20, &[0x48, 0x89, 0x5C, 0x24, 0x08]);
+        let target = target(&["48 89 5C 24 08", "48 89 5C"]);
+        assert_eq!(target.scan(&image, IMAGE_BASE).unwrap().signature_index, 0);
+    }
+
+    #[test]
+    fn failure_report_lists_every_signature() {
+        let image = image_with(0x20, &[0x90, 0x90]);
+        let signatures = ["48 89 5C 24 08", "40 53 48 83 EC 20", "E8 ?? ?? ?? ?? 48 8B D8 | offset 1, rel32"];
+        let target = target(&signatures);
+        assert_eq!(target.scan(&image, IMAGE_BASE), None);
+        assert_eq!(target.resolve_at(&image, IMAGE_BASE, 0x20), None);
+
+        let report = target.failure_report("1.21.0");
+        assert!(report[0].contains("none of the 3 signatures matched"));
+        assert!(report[0].contains("1.21.0"));
+        for (index, signature) in target.signatures.iter().enumerate() {
+            assert_eq!(report[index + 1], format!("  #{}: {}", index + 1, signature));
+        }
+        assert_eq!(report.len(), signatures.len() + 2);
+    }
+}
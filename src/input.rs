@@ -0,0 +1,419 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 仮想キーコードの数（0 ~ 255）
+const KEY_COUNT: usize = 256;
+
+const VK_SHIFT: i32 = 0x10;
+const VK_CONTROL: i32 = 0x11;
+const VK_MENU: i32 = 0x12;
+
+/// マウスホイールの方向
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WheelAxis {
+    /// 通常のホイール（上方向が正）
+    Vertical,
+    /// チルトホイールやタッチパッドの横スクロール（右方向が正）
+    Horizontal,
+}
+
+/// 入力イベント
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    /// キーが押された・離された（仮想キーコード）
+    Key { key: i32, down: bool },
+    /// マウスホイール（120 で1ノッチ。高精度のホイールやタッチパッドはより小さい値を送る）
+    Wheel { axis: WheelAxis, delta: i32 },
+}
+
+/// 発生時刻つきの入力イベント
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedEvent {
+    /// `input::now` と同じ基準の時刻
+    pub time: Duration,
+    pub event: InputEvent,
+}
+
+impl TimedEvent {
+    pub fn new(time: Duration, event: InputEvent) -> Self {
+        Self { time, event }
+    }
+}
+
+/// イベントの時刻の基準（最初に呼び出したときからの経過時間）
+pub fn now() -> Duration {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed()
+}
+
+/// 入力イベントの供給元
+pub trait InputSource: Send + Sync {
+    /// 前回から溜まったイベントを古い順に `out` に追加する
+    fn drain_into(&self, out: &mut Vec<TimedEvent>);
+}
+
+/// フックのスレッドから描画スレッドへイベントを渡すキュー
+///
+/// 描画が止まっている間にあふれた場合は古いイベントから捨てる。
+pub struct EventQueue {
+    events: Mutex<VecDeque<TimedEvent>>,
+}
+
+impl EventQueue {
+    /// 保持するイベントの最大数
+    pub const CAPACITY: usize = 512;
+
+    pub const fn new() -> Self {
+        Self { events: Mutex::new(VecDeque::new()) }
+    }
+
+    pub fn push(&self, event: TimedEvent) {
+        let mut events = self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if events.len() >= Self::CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    pub fn clear(&self) {
+        self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+    }
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputSource for EventQueue {
+    fn drain_into(&self, out: &mut Vec<TimedEvent>) {
+        let mut events = self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        out.extend(events.drain(..));
+    }
+}
+
+/// あらかじめ決めたイベントをフレームごとに返す供給元（テスト用）
+///
+/// `drain_into` を1回呼ぶごとに1フレーム分のイベントを返し、なくなったら何も返さない。
+#[derive(Default)]
+pub struct ScriptedInput {
+    frames: Mutex<VecDeque<Vec<TimedEvent>>>,
+}
+
+impl ScriptedInput {
+    pub fn new(frames: Vec<Vec<TimedEvent>>) -> Self {
+        Self { frames: Mutex::new(frames.into()) }
+    }
+
+    /// 1フレーム分のイベントを追加
+    pub fn push_frame(&self, events: Vec<TimedEvent>) {
+        self.frames.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push_back(events);
+    }
+
+    /// 残りのフレーム数
+    pub fn remaining(&self) -> usize {
+        self.frames.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).len()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn drain_into(&self, out: &mut Vec<TimedEvent>) {
+        let frame = self.frames.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).pop_front();
+        out.extend(frame.unwrap_or_default());
+    }
+}
+
+/// 左右を区別するキーコードから、区別しないキーコードを返す (例: LSHIFT → SHIFT)
+fn generic_key(key: i32) -> Option<i32> {
+    match key {
+        0xA0 | 0xA1 => Some(VK_SHIFT),
+        0xA2 | 0xA3 => Some(VK_CONTROL),
+        0xA4 | 0xA5 => Some(VK_MENU),
+        _ => None,
+    }
+}
+
+fn key_index(key: i32) -> Option<usize> {
+    usize::try_from(key).ok().filter(|&index| index < KEY_COUNT)
+}
+
+/// 1フレームの間に受け取った入力
+#[derive(Clone, Debug)]
+pub struct FrameInput {
+    held: [bool; KEY_COUNT],
+    pressed: [bool; KEY_COUNT],
+    released: [bool; KEY_COUNT],
+    /// 縦ホイールの合計
+    pub wheel_delta: i32,
+    /// 横ホイールの合計
+    pub hwheel_delta: i32,
+    /// このフレームで処理したイベント
+    pub events: Vec<TimedEvent>,
+}
+
+impl FrameInput {
+    /// フレームの終わりにキーが押されている
+    pub fn is_held(&self, key: i32) -> bool {
+        key_index(key).is_some_and(|index| self.held[index])
+    }
+
+    /// フレームの間にキーが押された
+    pub fn was_pressed(&self, key: i32) -> bool {
+        key_index(key).is_some_and(|index| self.pressed[index])
+    }
+
+    /// フレームの間にキーが離された
+    pub fn was_released(&self, key: i32) -> bool {
+        key_index(key).is_some_and(|index| self.released[index])
+    }
+
+    /// キーが押されているか、フレームの間に押された（フレームの間に離された短いタップも逃さない）
+    pub fn is_active(&self, key: i32) -> bool {
+        self.is_held(key) || self.was_pressed(key)
+    }
+}
+
+/// イベントからキーの押下状態を追跡する
+#[derive(Clone, Debug)]
+pub struct KeyTracker {
+    held: [bool; KEY_COUNT],
+}
+
+impl KeyTracker {
+    pub const fn new() -> Self {
+        Self { held: [false; KEY_COUNT] }
+    }
+
+    /// 供給元からイベントを取り出して1フレーム進める
+    pub fn advance(&mut self, source: &dyn InputSource) -> FrameInput {
+        let mut events = Vec::new();
+        source.drain_into(&mut events);
+        self.apply(events)
+    }
+
+    /// イベントを順に適用し、このフレームの入力を返す
+    pub fn apply(&mut self, events: Vec<TimedEvent>) -> FrameInput {
+        let mut frame = FrameInput {
+            held: self.held,
+            pressed: [false; KEY_COUNT],
+            released: [false; KEY_COUNT],
+            wheel_delta: 0,
+            hwheel_delta: 0,
+            events: Vec::new(),
+        };
+
+        for timed in &events {
+            match timed.event {
+                InputEvent::Key { key, down } => {
+                    self.set_key(&mut frame, key, down);
+                    if let Some(generic) = generic_key(key) {
+                        // 左右どちらかが押されていれば区別しないキーも押されている
+                        let sides = match generic {
+                            VK_SHIFT => [0xA0, 0xA1],
+                            VK_CONTROL => [0xA2, 0xA3],
+                            _ => [0xA4, 0xA5],
+                        };
+                        let any_side = sides.iter().any(|&side| self.is_held(side));
+                        self.set_key(&mut frame, generic, any_side);
+                    }
+                }
+                InputEvent::Wheel { axis: WheelAxis::Vertical, delta } => {
+                    frame.wheel_delta = frame.wheel_delta.saturating_add(delta);
+                }
+                InputEvent::Wheel { axis: WheelAxis::Horizontal, delta } => {
+                    frame.hwheel_delta = frame.hwheel_delta.saturating_add(delta);
+                }
+            }
+        }
+
+        frame.held = self.held;
+        frame.events = events;
+        frame
+    }
+
+    fn set_key(&mut self, frame: &mut FrameInput, key: i32, down: bool) {
+        let Some(index) = key_index(key) else {
+            return;
+        };
+        // キーリピートによる連続した押下は無視
+        if self.held[index] == down {
+            return;
+        }
+        self.held[index] = down;
+        if down {
+            frame.pressed[index] = true;
+        } else {
+            frame.released[index] = true;
+        }
+    }
+
+    pub fn is_held(&self, key: i32) -> bool {
+        key_index(key).is_some_and(|index| self.held[index])
+    }
+
+    /// すべてのキーを離した状態に戻す（フォーカスを失って離したイベントを受け取れない場合など）
+    pub fn release_all(&mut self) {
+        self.held = [false; KEY_COUNT];
+    }
+}
+
+impl Default for KeyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// マウスボタンの仮想キーコード（左・右・中・X1・X2）
+pub const MOUSE_BUTTON_KEYS: [i32; 5] = [0x01, 0x02, 0x04, 0x05, 0x06];
+
+/// マウスボタンの仮想キーコードか
+pub fn is_mouse_button(key: i32) -> bool {
+    MOUSE_BUTTON_KEYS.contains(&key)
+}
+
+/// 設定から決まる、必要なフック
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HookRequirements {
+    /// ホイールかマウスボタンを使うため、マウスフックが必要
+    pub mouse: bool,
+}
+
+impl HookRequirements {
+    /// ホイールで調整するか、トリガーにマウスボタンを使う場合だけマウスフックを必要とする
+    pub fn from_usage(uses_wheel: bool, trigger_keys: &[i32]) -> Self {
+        Self { mouse: uses_wheel || trigger_keys.iter().any(|&key| is_mouse_button(key)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VK_A: i32 = 0x41;
+    const VK_LSHIFT: i32 = 0xA0;
+    const VK_RSHIFT: i32 = 0xA1;
+
+    fn key(time_ms: u64, key: i32, down: bool) -> TimedEvent {
+        TimedEvent::new(Duration::from_millis(time_ms), InputEvent::Key { key, down })
+    }
+
+    fn wheel(time_ms: u64, axis: WheelAxis, delta: i32) -> TimedEvent {
+        TimedEvent::new(Duration::from_millis(time_ms), InputEvent::Wheel { axis, delta })
+    }
+
+    #[test]
+    fn tap_between_frames_is_active_for_one_frame() {
+        let source = ScriptedInput::new(vec![vec![key(0, VK_A, true), key(5, VK_A, false)], vec![]]);
+        let mut tracker = KeyTracker::new();
+
+        let frame = tracker.advance(&source);
+        assert!(!frame.is_held(VK_A));
+        assert!(frame.was_pressed(VK_A));
+        assert!(frame.was_released(VK_A));
+        assert!(frame.is_active(VK_A));
+
+        let frame = tracker.advance(&source);
+        assert!(!frame.is_active(VK_A));
+        assert_eq!(source.remaining(), 0);
+    }
+
+    #[test]
+    fn key_repeat_is_not_a_new_press() {
+        let source = ScriptedInput::new(vec![
+            vec![key(0, VK_A, true)],
+            vec![key(30, VK_A, true), key(60, VK_A, true)],
+            vec![key(90, VK_A, false)],
+        ]);
+        let mut tracker = KeyTracker::new();
+
+        assert!(tracker.advance(&source).was_pressed(VK_A));
+        let frame = tracker.advance(&source);
+        assert!(frame.is_held(VK_A));
+        assert!(!frame.was_pressed(VK_A));
+        let frame = tracker.advance(&source);
+        assert!(frame.was_released(VK_A));
+        assert!(!frame.is_held(VK_A));
+    }
+
+    #[test]
+    fn left_and_right_modifiers_fold_into_the_generic_key() {
+        let source = ScriptedInput::new(vec![
+            vec![key(0, VK_LSHIFT, true)],
+            vec![key(10, VK_RSHIFT, true), key(20, VK_LSHIFT, false)],
+            vec![key(30, VK_RSHIFT, false)],
+        ]);
+        let mut tracker = KeyTracker::new();
+
+        let frame = tracker.advance(&source);
+        assert!(frame.is_held(VK_SHIFT));
+        assert!(frame.was_pressed(VK_SHIFT));
+
+        // どちらかが押されている間は区別しないキーも押されたまま
+        let frame = tracker.advance(&source);
+        assert!(frame.is_held(VK_SHIFT));
+        assert!(!frame.was_pressed(VK_SHIFT));
+        assert!(!frame.was_released(VK_SHIFT));
+
+        let frame = tracker.advance(&source);
+        assert!(!frame.is_held(VK_SHIFT));
+        assert!(frame.was_released(VK_SHIFT));
+    }
+
+    #[test]
+    fn wheel_deltas_are_summed_per_axis() {
+        let mut tracker = KeyTracker::new();
+        let frame = tracker.apply(vec![
+            wheel(0, WheelAxis::Vertical, 120),
+            wheel(1, WheelAxis::Horizontal, -30),
+            wheel(2, WheelAxis::Vertical, 40),
+        ]);
+        assert_eq!(frame.wheel_delta, 160);
+        assert_eq!(frame.hwheel_delta, -30);
+        assert_eq!(frame.events.len(), 3);
+    }
+
+    #[test]
+    fn out_of_range_keys_are_ignored() {
+        let mut tracker = KeyTracker::new();
+        let frame = tracker.apply(vec![key(0, -1, true), key(0, 256, true)]);
+        assert!(!frame.is_active(-1));
+        assert!(!frame.is_active(256));
+    }
+
+    #[test]
+    fn release_all_forgets_held_keys() {
+        let mut tracker = KeyTracker::new();
+        tracker.apply(vec![key(0, VK_A, true)]);
+        tracker.release_all();
+        assert!(!tracker.is_held(VK_A));
+        assert!(!tracker.apply(Vec::new()).is_held(VK_A));
+    }
+
+    #[test]
+    fn event_queue_drops_oldest_on_overflow() {
+        let queue = EventQueue::new();
+        let extra = 10;
+        for index in 0..EventQueue::CAPACITY + extra {
+            queue.push(wheel(index as u64, WheelAxis::Vertical, 1));
+        }
+
+        let mut events = Vec::new();
+        queue.drain_into(&mut events);
+        assert_eq!(events.len(), EventQueue::CAPACITY);
+        assert_eq!(events[0].time, Duration::from_millis(extra as u64));
+        assert_eq!(events.last().unwrap().time, Duration::from_millis((EventQueue::CAPACITY + extra - 1) as u64));
+
+        events.clear();
+        queue.drain_into(&mut events);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn hook_requirements_need_mouse_only_for_wheel_or_buttons() {
+        assert!(!HookRequirements::from_usage(false, &[VK_A]).mouse);
+        assert!(HookRequirements::from_usage(true, &[VK_A]).mouse);
+        assert!(HookRequirements::from_usage(false, &[VK_A, 0x05]).mouse);
+    }
+}
@@ -0,0 +1,216 @@
+/// ズームの入力を受け付けるかを決めるゲームの状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputContext {
+    /// 通常のプレイ中
+    Gameplay,
+    /// チャットを開いている
+    Chat,
+    /// チャット以外の画面（インベントリ、メニュー、テキスト入力など）を開いている
+    Menu,
+    /// ゲームのウィンドウにフォーカスがない
+    Unfocused,
+}
+
+impl InputContext {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gameplay => "gameplay",
+            Self::Chat => "chat",
+            Self::Menu => "menu",
+            Self::Unfocused => "unfocused",
+        }
+    }
+}
+
+/// 状態ごとにズームの入力を無視するかどうか
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SuppressionSettings {
+    pub in_chat: bool,
+    pub in_menus: bool,
+}
+
+impl SuppressionSettings {
+    /// この状態でズームの入力を受け付けるか（フォーカスがない場合は常に受け付けない）
+    pub fn allows(&self, context: InputContext) -> bool {
+        match context {
+            InputContext::Gameplay => true,
+            InputContext::Chat => !self.in_chat,
+            InputContext::Menu => !self.in_menus,
+            InputContext::Unfocused => false,
+        }
+    }
+}
+
+/// フレームごとに観測した値
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContextSample {
+    pub focused: bool,
+    /// マウスカーソルが表示されている（ゲームは画面を開いている間だけカーソルを表示する）
+    pub cursor_visible: bool,
+    /// チャットを開くキーのいずれかが押されている
+    pub chat_key_down: bool,
+}
+
+/// カーソルの表示状態とチャットキーから現在の状態を推定する
+///
+/// プレイ中にチャットキーが押されてから `CHAT_OPEN_FRAMES` フレーム以内にカーソルが表示された場合、
+/// カーソルが再び隠れるまでをチャットとみなす。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ContextTracker {
+    // チャットキーが押されてからカーソルの表示を待つ残りフレーム数
+    chat_pending_frames: u32,
+    chat_key_was_down: bool,
+    in_chat: bool,
+}
+
+impl ContextTracker {
+    /// チャットキーを押してから画面が開くまで待つフレーム数
+    pub const CHAT_OPEN_FRAMES: u32 = 30;
+
+    pub const fn new() -> Self {
+        Self { chat_pending_frames: 0, chat_key_was_down: false, in_chat: false }
+    }
+
+    pub fn update(&mut self, sample: ContextSample) -> InputContext {
+        let chat_key_pressed = sample.chat_key_down && !self.chat_key_was_down;
+        self.chat_key_was_down = sample.chat_key_down;
+
+        if !sample.focused {
+            self.chat_pending_frames = 0;
+            return InputContext::Unfocused;
+        }
+
+        if !sample.cursor_visible {
+            self.in_chat = false;
+            if chat_key_pressed {
+                self.chat_pending_frames = Self::CHAT_OPEN_FRAMES;
+            } else {
+                self.chat_pending_frames = self.chat_pending_frames.saturating_sub(1);
+            }
+            return InputContext::Gameplay;
+        }
+
+        if self.chat_pending_frames > 0 {
+            self.in_chat = true;
+            self.chat_pending_frames = 0;
+        }
+        if self.in_chat {
+            InputContext::Chat
+        } else {
+            InputContext::Menu
+        }
+    }
+}
+
+/// ズームキーの入力を状態に応じて通す
+///
+/// 受け付けない状態で押されたキーは、受け付ける状態に戻っても一度離すまで無視する
+/// （チャットを閉じたときや、他のアプリで押したままゲームに切り替えたときにズームしないように）。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KeyGate {
+    // キーを離すまで入力を無視する
+    blocked_until_release: bool,
+}
+
+impl KeyGate {
+    pub const fn new() -> Self {
+        Self { blocked_until_release: false }
+    }
+
+    /// キーの物理的な状態から、ズームに使うキーの状態を返す
+    pub fn filter(&mut self, allowed: bool, pressed: bool) -> bool {
+        if !pressed {
+            self.blocked_until_release = false;
+            return false;
+        }
+        if !allowed {
+            self.blocked_until_release = true;
+        }
+        !self.blocked_until_release
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::focus::{FakeFocus, FocusProvider};
+    use crate::input::WheelAxis;
+    use crate::wheel::{wheel_action, HorizontalWheelAction, WheelAction, WheelSettings};
+
+    const SUPPRESS_ALL: SuppressionSettings = SuppressionSettings { in_chat: true, in_menus: true };
+
+    /// zoom.rs と同じ順に、フォーカスの判定から状態を推定してキーを通す
+    fn gated_key(focus: &FakeFocus, tracker: &mut ContextTracker, gate: &mut KeyGate, pressed: bool) -> bool {
+        let sample = ContextSample { focused: focus.is_game_focused(), ..ContextSample::default() };
+        let context = tracker.update(sample);
+        gate.filter(SUPPRESS_ALL.allows(context), pressed)
+    }
+
+    #[test]
+    fn allows_depends_on_context_and_settings() {
+        let none = SuppressionSettings { in_chat: false, in_menus: false };
+        assert!(none.allows(InputContext::Gameplay));
+        assert!(none.allows(InputContext::Chat));
+        assert!(none.allows(InputContext::Menu));
+        assert!(!none.allows(InputContext::Unfocused));
+
+        assert!(SUPPRESS_ALL.allows(InputContext::Gameplay));
+        assert!(!SUPPRESS_ALL.allows(InputContext::Chat));
+        assert!(!SUPPRESS_ALL.allows(InputContext::Menu));
+        assert!(!SUPPRESS_ALL.allows(InputContext::Unfocused));
+    }
+
+    #[test]
+    fn key_held_across_focus_loss_is_released() {
+        let focus = FakeFocus::new(true);
+        let mut tracker = ContextTracker::new();
+        let mut gate = KeyGate::new();
+
+        assert!(gated_key(&focus, &mut tracker, &mut gate, true));
+        focus.set(false);
+        assert!(!gated_key(&focus, &mut tracker, &mut gate, true));
+        // フォーカスが戻っても離すまでは押していない扱い
+        focus.set(true);
+        assert!(!gated_key(&focus, &mut tracker, &mut gate, true));
+    }
+
+    #[test]
+    fn key_pressed_while_unfocused_is_ignored_after_focus() {
+        let focus = FakeFocus::new(false);
+        let mut tracker = ContextTracker::new();
+        let mut gate = KeyGate::new();
+
+        assert!(!gated_key(&focus, &mut tracker, &mut gate, true));
+        focus.set(true);
+        assert!(!gated_key(&focus, &mut tracker, &mut gate, true));
+    }
+
+    #[test]
+    fn release_then_repress_is_accepted() {
+        let focus = FakeFocus::new(false);
+        let mut tracker = ContextTracker::new();
+        let mut gate = KeyGate::new();
+
+        assert!(!gated_key(&focus, &mut tracker, &mut gate, true));
+        focus.set(true);
+        assert!(!gated_key(&focus, &mut tracker, &mut gate, true));
+        assert!(!gated_key(&focus, &mut tracker, &mut gate, false));
+        assert!(gated_key(&focus, &mut tracker, &mut gate, true));
+    }
+
+    #[test]
+    fn wheel_is_forwarded_while_unfocused() {
+        let focus = FakeFocus::new(true);
+        let settings = WheelSettings {
+            scroll_adjustment: true,
+            consume_while_zooming: true,
+            horizontal: HorizontalWheelAction::None,
+        };
+
+        let action = wheel_action(settings, WheelAxis::Vertical, focus.is_game_focused(), true);
+        assert_eq!(action, WheelAction::Consume);
+        focus.set(false);
+        let action = wheel_action(settings, WheelAxis::Vertical, focus.is_game_focused(), true);
+        assert_eq!(action, WheelAction::Forward);
+    }
+}
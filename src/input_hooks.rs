@@ -0,0 +1,319 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+
+use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, VK_LBUTTON, VK_MBUTTON, VK_RBUTTON, VK_XBUTTON1, VK_XBUTTON2,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, GetMessageW, PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx, HHOOK,
+    KBDLLHOOKSTRUCT, MSG, MSLLHOOKSTRUCT, WH_KEYBOARD_LL, WH_MOUSE_LL, WM_APP, WM_KEYDOWN, WM_KEYUP,
+    WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEWHEEL, WM_QUIT,
+    WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_XBUTTONDOWN, WM_XBUTTONUP,
+};
+
+use crate::focus::FocusProvider;
+use crate::foreground_focus::ForegroundFocus;
+use crate::input::{self, EventQueue, HookRequirements, InputEvent, TimedEvent, WheelAxis};
+use crate::logger;
+use crate::wheel::{wheel_action, HorizontalWheelAction, WheelAction, WheelSettings};
+
+/// フックのスレッドで受け取り、描画スレッドで処理するイベント
+pub static INPUT_EVENTS: EventQueue = EventQueue::new();
+
+static MOUSE_HOOK: AtomicUsize = AtomicUsize::new(0);
+static KEYBOARD_HOOK: AtomicUsize = AtomicUsize::new(0);
+static HOOK_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+static MOUSE_HOOK_WANTED: AtomicBool = AtomicBool::new(false);
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+static HOOK_THREAD: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+static FOCUS: ForegroundFocus = ForegroundFocus::new();
+
+// フックのスレッドから設定ファイルを読まないよう、描画スレッドが毎フレーム写しておく
+static ZOOM_KEY_HELD: AtomicBool = AtomicBool::new(false);
+static WHEEL_SETTINGS: Mutex<WheelSettings> = Mutex::new(WheelSettings {
+    scroll_adjustment: false,
+    consume_while_zooming: false,
+    horizontal: HorizontalWheelAction::None,
+});
+
+/// マウスフックを設定に合わせるようフックのスレッドに依頼するメッセージ
+const WM_SYNC_HOOKS: u32 = WM_APP + 1;
+
+// キーボードフックを使えない場合にポーリングで検出したキーの状態
+static POLLED_KEYS: Mutex<[bool; 256]> = Mutex::new([false; 256]);
+
+/// マウスフックがホイールイベントを消費するかの判断に使う状態を更新
+pub fn publish_wheel_state(settings: WheelSettings, zoom_key_held: bool) {
+    ZOOM_KEY_HELD.store(zoom_key_held, Ordering::Relaxed);
+    if let Ok(mut current) = WHEEL_SETTINGS.lock() {
+        *current = settings;
+    }
+}
+
+/// キーボードフックが動作しているか
+pub fn is_keyboard_hooked() -> bool {
+    KEYBOARD_HOOK.load(Ordering::Relaxed) != 0
+}
+
+/// キーボードフックを使えない場合に、指定したキーの状態の変化をイベントとして追加する
+pub fn poll_keys(keys: &[i32]) {
+    let Ok(mut polled) = POLLED_KEYS.lock() else {
+        return;
+    };
+    let time = input::now();
+    for &key in keys {
+        let Some(state) = usize::try_from(key).ok().and_then(|index| polled.get_mut(index)) else {
+            continue;
+        };
+        let down = unsafe { GetAsyncKeyState(key) } & 0x8000u16 as i16 != 0;
+        if *state != down {
+            *state = down;
+            INPUT_EVENTS.push(TimedEvent::new(time, InputEvent::Key { key, down }));
+        }
+    }
+}
+
+/// キーボードのフックプロシージャ
+unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+        let keyboard_struct = lparam.0 as *const KBDLLHOOKSTRUCT;
+        let down = match wparam.0 as u32 {
+            WM_KEYDOWN | WM_SYSKEYDOWN => Some(true),
+            WM_KEYUP | WM_SYSKEYUP => Some(false),
+            _ => None,
+        };
+        if let (Some(down), false) = (down, keyboard_struct.is_null()) {
+            let key = (*keyboard_struct).vkCode as i32;
+            INPUT_EVENTS.push(TimedEvent::new(input::now(), InputEvent::Key { key, down }));
+        }
+    }
+    
+    let hook = HHOOK(KEYBOARD_HOOK.load(Ordering::Relaxed) as isize);
+    CallNextHookEx(hook, code, wparam, lparam)
+}
+
+/// マウスボタンのメッセージを (仮想キーコード, 押下) に変換
+fn mouse_button(message: u32, mouse_data: u32) -> Option<(i32, bool)> {
+    // X ボタンは mouseData の上位ワードで区別する (1: XBUTTON1, 2: XBUTTON2)
+    let x_button = match mouse_data >> 16 {
+        1 => VK_XBUTTON1.0 as i32,
+        _ => VK_XBUTTON2.0 as i32,
+    };
+    match message {
+        WM_LBUTTONDOWN => Some((VK_LBUTTON.0 as i32, true)),
+        WM_LBUTTONUP => Some((VK_LBUTTON.0 as i32, false)),
+        WM_RBUTTONDOWN => Some((VK_RBUTTON.0 as i32, true)),
+        WM_RBUTTONUP => Some((VK_RBUTTON.0 as i32, false)),
+        WM_MBUTTONDOWN => Some((VK_MBUTTON.0 as i32, true)),
+        WM_MBUTTONUP => Some((VK_MBUTTON.0 as i32, false)),
+        WM_XBUTTONDOWN => Some((x_button, true)),
+        WM_XBUTTONUP => Some((x_button, false)),
+        _ => None,
+    }
+}
+
+/// マウスのフックプロシージャ（ホイールとボタン）
+unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let mouse_struct = lparam.0 as *const MSLLHOOKSTRUCT;
+    if code >= 0 && !mouse_struct.is_null() {
+        let message = wparam.0 as u32;
+        let mouse_data = (*mouse_struct).mouseData;
+        let axis = match message {
+            WM_MOUSEWHEEL => Some(WheelAxis::Vertical),
+            WM_MOUSEHWHEEL => Some(WheelAxis::Horizontal),
+            _ => None,
+        };
+        
+        if let Some(axis) = axis {
+            // mouseDataの上位ワードにホイールデルタが含まれている（120単位で1ノッチ）
+            let delta = (mouse_data >> 16) as i16 as i32;
+            // 他のアプリでのスクロールは無視
+            let focused = FOCUS.is_game_focused();
+            if focused {
+                INPUT_EVENTS.push(TimedEvent::new(input::now(), InputEvent::Wheel { axis, delta }));
+            }
+            
+            // ズームの調整に使ったスクロールはゲームに渡さない
+            let settings = *WHEEL_SETTINGS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if wheel_action(settings, axis, focused, ZOOM_KEY_HELD.load(Ordering::Relaxed)) == WheelAction::Consume {
+                return LRESULT(1);
+            }
+        } else if let Some((key, down)) = mouse_button(message, mouse_data) {
+            // キーボードフックはマウスボタンを受け取らないため、トリガー用にここでイベントにする
+            INPUT_EVENTS.push(TimedEvent::new(input::now(), InputEvent::Key { key, down }));
+        }
+    }
+    
+    let hook = HHOOK(MOUSE_HOOK.load(Ordering::Relaxed) as isize);
+    CallNextHookEx(hook, code, wparam, lparam)
+}
+
+fn unhook(slot: &AtomicUsize) {
+    let hook = HHOOK(slot.swap(0, Ordering::Relaxed) as isize);
+    if hook.0 != 0 {
+        unsafe {
+            let _ = UnhookWindowsHookEx(hook);
+        }
+    }
+}
+
+/// フックの状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputHookStatus {
+    pub keyboard: bool,
+    pub mouse: bool,
+}
+
+impl fmt::Display for InputHookStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = |installed: bool| if installed { "installed" } else { "not installed" };
+        write!(f, "keyboard hook {}, mouse hook {}", state(self.keyboard), state(self.mouse))
+    }
+}
+
+/// 現在のフックの状態
+pub fn status() -> InputHookStatus {
+    InputHookStatus {
+        keyboard: is_keyboard_hooked(),
+        mouse: MOUSE_HOOK.load(Ordering::Relaxed) != 0,
+    }
+}
+
+/// フックのスレッドでマウスフックを設定に合わせる
+unsafe fn sync_mouse_hook() {
+    let wanted = MOUSE_HOOK_WANTED.load(Ordering::SeqCst);
+    let installed = MOUSE_HOOK.load(Ordering::Relaxed) != 0;
+    if wanted == installed {
+        return;
+    }
+    if wanted {
+        match SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), None, 0) {
+            Ok(hook) => {
+                MOUSE_HOOK.store(hook.0 as usize, Ordering::Relaxed);
+                // 設定中に stop() が呼ばれた場合はすぐに解除する
+                if !MOUSE_HOOK_WANTED.load(Ordering::SeqCst) {
+                    unhook(&MOUSE_HOOK);
+                    return;
+                }
+                logger::info("mouse hook installed");
+            }
+            Err(error) => logger::warn(&format!(
+                "failed to install the mouse hook ({}); scrolling and mouse button triggers are disabled",
+                error
+            )),
+        }
+    } else {
+        unhook(&MOUSE_HOOK);
+        logger::info("mouse hook removed (not needed by the current config)");
+    }
+}
+
+/// 設定に合わせてマウスフックを設定・解除する（変化したときだけフックのスレッドに依頼する）
+pub fn set_requirements(requirements: HookRequirements) {
+    // 停止後に設定を再読み込みしてもフックを設定し直さない
+    if STOP_REQUESTED.load(Ordering::SeqCst) {
+        return;
+    }
+    if MOUSE_HOOK_WANTED.swap(requirements.mouse, Ordering::SeqCst) == requirements.mouse {
+        return;
+    }
+    // スレッドの開始前であれば、スレッドが開始時に反映する
+    let thread_id = HOOK_THREAD_ID.load(Ordering::SeqCst);
+    if thread_id != 0 {
+        unsafe {
+            let _ = PostThreadMessageW(thread_id, WM_SYNC_HOOKS, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+/// キーボード・マウスフック用のメッセージループスレッドを開始
+pub fn start(requirements: HookRequirements) {
+    STOP_REQUESTED.store(false, Ordering::SeqCst);
+    MOUSE_HOOK_WANTED.store(requirements.mouse, Ordering::SeqCst);
+    let handle = thread::spawn(|| {
+        unsafe {
+            // 設定の変更と終了時にメッセージを送るためにスレッドIDを記録
+            HOOK_THREAD_ID.store(GetCurrentThreadId(), Ordering::SeqCst);
+            
+            match SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook_proc), None, 0) {
+                Ok(hook) => {
+                    KEYBOARD_HOOK.store(hook.0 as usize, Ordering::Relaxed);
+                    // 設定中に stop() が呼ばれた場合はすぐに解除する
+                    if STOP_REQUESTED.load(Ordering::SeqCst) {
+                        unhook(&KEYBOARD_HOOK);
+                    }
+                }
+                Err(error) => logger::warn(&format!(
+                    "failed to install the keyboard hook ({}); falling back to polling key state",
+                    error
+                )),
+            }
+            sync_mouse_hook();
+            logger::info(&format!("input hooks: {}", status()));
+            
+            // メッセージループ（フックが動作するために必要、WM_QUIT で終了）
+            let mut msg: MSG = std::mem::zeroed();
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                if msg.message == WM_SYNC_HOOKS {
+                    sync_mouse_hook();
+                }
+            }
+            
+            unhook(&KEYBOARD_HOOK);
+            unhook(&MOUSE_HOOK);
+            HOOK_THREAD_ID.store(0, Ordering::SeqCst);
+        }
+    });
+    if let Ok(mut thread) = HOOK_THREAD.lock() {
+        *thread = Some(handle);
+    }
+}
+
+/// フックをこの場で解除する（UnhookWindowsHookEx はどのスレッドからでも呼べる）
+pub fn stop() {
+    STOP_REQUESTED.store(true, Ordering::SeqCst);
+    MOUSE_HOOK_WANTED.store(false, Ordering::SeqCst);
+    unhook(&KEYBOARD_HOOK);
+    unhook(&MOUSE_HOOK);
+    INPUT_EVENTS.clear();
+}
+
+/// フックのスレッドに終了を依頼するだけで待たない（DllMain から呼ぶ）
+///
+/// フックはスレッドがメッセージループを抜けたときに解除する。
+pub fn request_stop() {
+    STOP_REQUESTED.store(true, Ordering::SeqCst);
+    MOUSE_HOOK_WANTED.store(false, Ordering::SeqCst);
+    let thread_id = HOOK_THREAD_ID.load(Ordering::SeqCst);
+    if thread_id != 0 {
+        unsafe {
+            let _ = PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+/// メッセージループを終了させてスレッドの終了を待つ（ローダーロック中に呼ばないこと）
+pub fn join() {
+    let handle = match HOOK_THREAD.lock() {
+        Ok(mut thread) => match thread.take() {
+            Some(handle) => handle,
+            None => return,
+        },
+        Err(_) => return,
+    };
+    // スレッドIDの記録前であれば、記録されるまで待ってから終了を依頼する
+    while !handle.is_finished() {
+        let thread_id = HOOK_THREAD_ID.load(Ordering::SeqCst);
+        if thread_id != 0 && unsafe { PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) }.is_ok() {
+            break;
+        }
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+    if handle.join().is_err() {
+        logger::warn("input hook thread panicked");
+    }
+}
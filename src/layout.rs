@@ -0,0 +1,170 @@
+use crate::fov_patch::{FovFields, FovPair};
+use crate::memory::{Field, MemoryError, ObjectRef};
+use crate::offsets::GameOffsets;
+
+/// LevelRenderer のうち使用するフィールド
+#[derive(Clone, Copy, Debug)]
+pub struct LevelRendererLayout {
+    /// LevelRendererPlayer へのポインタ
+    pub player: Field<usize>,
+    /// オブジェクトのサイズ（分からなければ使用するフィールドの終端まで）
+    pub size: usize,
+}
+
+impl LevelRendererLayout {
+    pub fn from_offsets(offsets: &GameOffsets) -> Self {
+        let player = Field::new(offsets.level_renderer_player);
+        Self { player, size: offsets.level_renderer_size.unwrap_or(player.end()) }
+    }
+}
+
+/// LevelRendererPlayer のうち使用するフィールド
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerLayout {
+    /// 水平FOV（投影行列のスケール）
+    pub fov_x: Field<f32>,
+    /// 垂直FOV（投影行列のスケール）
+    pub fov_y: Field<f32>,
+    /// オブジェクトのサイズ（分からなければ使用するフィールドの終端まで）
+    pub size: usize,
+}
+
+impl PlayerLayout {
+    pub fn from_offsets(offsets: &GameOffsets) -> Self {
+        let fov_x = Field::new(offsets.fov_x);
+        let fov_y = Field::new(offsets.fov_y);
+        let fields_end = fov_x.end().max(fov_y.end());
+        Self { fov_x, fov_y, size: offsets.player_size.unwrap_or(fields_end) }
+    }
+}
+
+/// LevelRendererPlayer 上のFOVフィールド
+pub struct PlayerFov<'a> {
+    object: ObjectRef<'a>,
+    layout: PlayerLayout,
+}
+
+impl<'a> PlayerFov<'a> {
+    /// フィールドがオブジェクトの範囲内か検査して作成
+    pub fn new(object: ObjectRef<'a>, layout: PlayerLayout) -> Result<Self, MemoryError> {
+        object.check(layout.fov_x)?;
+        object.check(layout.fov_y)?;
+        Ok(Self { object, layout })
+    }
+
+    /// LevelRenderer からプレイヤーを辿ってFOVフィールドを取得
+    ///
+    /// # Safety
+    /// `level_renderer` が null でない場合、オフセットに対応する LevelRenderer を指しており、
+    /// オフセットに記録したサイズだけ読み書きできること。
+    pub unsafe fn from_level_renderer(
+        level_renderer: *mut u8,
+        offsets: &GameOffsets,
+    ) -> Result<Self, MemoryError> {
+        let renderer_layout = LevelRendererLayout::from_offsets(offsets);
+        let player_layout = PlayerLayout::from_offsets(offsets);
+
+        let renderer = ObjectRef::from_raw(level_renderer, renderer_layout.size)?;
+        let player = renderer.follow(renderer_layout.player, player_layout.size)?;
+        Self::new(player, player_layout)
+    }
+
+    /// プレイヤーのアドレス（プレイヤーの切り替わり検出用）
+    pub fn address(&self) -> usize {
+        self.object.address()
+    }
+}
+
+impl FovFields for PlayerFov<'_> {
+    fn read(&self) -> FovPair {
+        // 範囲は new で検査済みのため失敗しない
+        (
+            self.object.read(self.layout.fov_x).unwrap_or(f32::NAN),
+            self.object.read(self.layout.fov_y).unwrap_or(f32::NAN),
+        )
+    }
+
+    fn write(&mut self, fov: FovPair) {
+        let _ = self.object.write(self.layout.fov_x, fov.0);
+        let _ = self.object.write(self.layout.fov_y, fov.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets() -> GameOffsets {
+        GameOffsets {
+            level_renderer_player: 0x08,
+            fov_x: 0x10,
+            fov_y: 0x14,
+            level_renderer_size: Some(0x10),
+            player_size: Some(0x20),
+            ..GameOffsets::default()
+        }
+    }
+
+    #[test]
+    fn reads_and_writes_fov_in_a_buffer() {
+        let mut buffer = [0u8; 0x20];
+        buffer[0x10..0x14].copy_from_slice(&1.5f32.to_le_bytes());
+        buffer[0x14..0x18].copy_from_slice(&2.5f32.to_le_bytes());
+
+        let layout = PlayerLayout::from_offsets(&offsets());
+        let mut fov = PlayerFov::new(ObjectRef::from_slice(&mut buffer), layout).unwrap();
+        assert_eq!(fov.read(), (1.5, 2.5));
+        fov.write((3.0, 4.0));
+        assert_eq!(fov.read(), (3.0, 4.0));
+        assert_eq!(&buffer[0x10..0x14], &3.0f32.to_le_bytes());
+    }
+
+    #[test]
+    fn rejects_fields_outside_the_player() {
+        // 実際のオブジェクトが記録したサイズより小さい場合
+        let mut buffer = [0u8; 0x16];
+        let layout = PlayerLayout { size: 0x16, ..PlayerLayout::from_offsets(&offsets()) };
+        let error = PlayerFov::new(ObjectRef::from_slice(&mut buffer), layout);
+        assert_eq!(error.err(), Some(MemoryError::OutOfBounds { offset: 0x14, size: 4, object_size: 0x16 }));
+    }
+
+    #[test]
+    fn follows_the_player_pointer() {
+        let mut player = [0u8; 0x20];
+        player[0x14..0x18].copy_from_slice(&0.75f32.to_le_bytes());
+        let mut renderer = [0u8; 0x10];
+        renderer[0x08..0x10].copy_from_slice(&(player.as_mut_ptr() as usize).to_le_bytes());
+
+        let fov = unsafe { PlayerFov::from_level_renderer(renderer.as_mut_ptr(), &offsets()) }.unwrap();
+        assert_eq!(fov.address(), player.as_ptr() as usize);
+        assert_eq!(fov.read().1, 0.75);
+    }
+
+    #[test]
+    fn rejects_null_pointers() {
+        let error = unsafe { PlayerFov::from_level_renderer(std::ptr::null_mut(), &offsets()) };
+        assert_eq!(error.err(), Some(MemoryError::NullPointer));
+
+        // LevelRendererPlayer が null
+        let mut renderer = [0u8; 0x10];
+        let error = unsafe { PlayerFov::from_level_renderer(renderer.as_mut_ptr(), &offsets()) };
+        assert_eq!(error.err(), Some(MemoryError::NullPointer));
+    }
+
+    #[test]
+    fn unknown_sizes_cover_the_used_fields() {
+        let offsets = GameOffsets { level_renderer_size: None, player_size: None, ..offsets() };
+        assert_eq!(LevelRendererLayout::from_offsets(&offsets).size, 0x10);
+        assert_eq!(PlayerLayout::from_offsets(&offsets).size, 0x18);
+    }
+
+    #[test]
+    fn builtin_layouts_fit_their_objects() {
+        let offsets = GameOffsets::default();
+        let renderer = LevelRendererLayout::from_offsets(&offsets);
+        let player = PlayerLayout::from_offsets(&offsets);
+        assert!(renderer.player.end() <= renderer.size);
+        assert!(player.fov_x.end() <= player.size);
+        assert!(player.fov_y.end() <= player.size);
+    }
+}
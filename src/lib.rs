@@ -4,6 +4,38 @@ mod windows;
 pub mod zoom;
 #[cfg(target_os = "windows")]
 pub mod config_manager;
+#[cfg(target_os = "windows")]
+pub mod foreground_focus;
+#[cfg(target_os = "windows")]
+pub mod input_hooks;
+#[cfg(target_os = "windows")]
+pub mod minhook_backend;
+#[cfg(target_os = "windows")]
+pub mod xinput_gamepad;
+pub mod focus;
+pub mod fov_guard;
+pub mod fov_math;
+pub mod fov_patch;
+pub mod gamepad;
+pub mod hook_backend;
+pub mod hook_cache;
+pub mod hook_target;
+pub mod input;
+pub mod input_context;
+pub mod layout;
+pub mod lifecycle;
+pub mod logger;
+pub mod memory;
+#[cfg(test)]
+pub mod mock_hook_backend;
+pub mod offsets;
+pub mod paths;
+pub mod prologue;
+pub mod retry;
+pub mod signature;
+pub mod version;
+pub mod wheel;
+pub mod zoom_controller;
 
 #[ctor::ctor]
 fn safe_setup() {
@@ -0,0 +1,286 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+
+use crate::hook_backend::HookError;
+use crate::logger;
+use crate::prologue::ForeignDetour;
+
+/// Modの状態
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecycleState {
+    /// まだ初期化していない
+    Uninitialized = 0,
+    /// 関数を探してフックを作成中
+    Scanning = 1,
+    /// フックが有効でズームできる
+    Hooked = 2,
+    /// フックは作成したが、異常を検出して無効化した
+    Degraded = 3,
+    /// 初期化に失敗した
+    Failed = 4,
+    /// 終了した
+    ShutDown = 5,
+}
+
+impl LifecycleState {
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => Self::Scanning,
+            2 => Self::Hooked,
+            3 => Self::Degraded,
+            4 => Self::Failed,
+            5 => Self::ShutDown,
+            _ => Self::Uninitialized,
+        }
+    }
+
+    /// 外部ツールに返す数値
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Uninitialized => "uninitialized",
+            Self::Scanning => "scanning",
+            Self::Hooked => "hooked",
+            Self::Degraded => "degraded",
+            Self::Failed => "failed",
+            Self::ShutDown => "shut down",
+        }
+    }
+
+    /// この状態から `next` に移れるか
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use LifecycleState::*;
+        matches!(
+            (self, next),
+            (Uninitialized, Scanning)
+                | (Uninitialized, Failed)
+                | (Scanning, Hooked)
+                | (Scanning, Failed)
+                | (Hooked, Degraded)
+                | (Uninitialized | Scanning | Hooked | Degraded | Failed, ShutDown)
+        )
+    }
+}
+
+impl fmt::Display for LifecycleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 初期化の失敗理由
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StartupError {
+    /// フックのバックエンドを初期化できない
+    Backend(HookError),
+    /// フック対象の関数が見つからない
+    TargetNotFound(&'static str),
+    /// 他のModが関数の先頭を書き換えており、設定によりフックしなかった
+    ForeignDetour { name: &'static str, detour: ForeignDetour },
+    /// フックを作成・有効化できない
+    Install { name: &'static str, error: HookError },
+    /// 初期化中に終了した
+    Cancelled,
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Backend(error) => write!(f, "failed to initialize the hook backend: {}", error),
+            Self::TargetNotFound(name) => write!(f, "{} was not found", name),
+            Self::ForeignDetour { name, detour } => {
+                write!(f, "{} is already detoured by another mod ({})", name, detour)
+            }
+            Self::Install { name, error } => write!(f, "failed to install the {} hook: {}", name, error),
+            Self::Cancelled => write!(f, "shut down during startup"),
+        }
+    }
+}
+
+/// 状態の遷移が許されていない
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidTransition {
+    pub from: LifecycleState,
+    pub to: LifecycleState,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot change state from {} to {}", self.from, self.to)
+    }
+}
+
+/// 状態と、失敗・縮退した場合の理由
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LifecycleStatus {
+    pub state: LifecycleState,
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for LifecycleStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            Some(reason) => write!(f, "{} ({})", self.state, reason),
+            None => write!(f, "{}", self.state),
+        }
+    }
+}
+
+/// Modの状態を管理する
+///
+/// 状態は遷移のたびにログに書き、`status` で外部から参照できる。
+pub struct Lifecycle {
+    state: AtomicU8,
+    // 遷移と理由の更新をまとめて行うためのロック
+    reason: Mutex<Option<String>>,
+}
+
+impl Lifecycle {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(LifecycleState::Uninitialized as u8),
+            reason: Mutex::new(None),
+        }
+    }
+
+    pub fn state(&self) -> LifecycleState {
+        LifecycleState::from_code(self.state.load(Ordering::Acquire))
+    }
+
+    pub fn status(&self) -> LifecycleStatus {
+        let reason = self.reason.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        LifecycleStatus { state: self.state(), reason: reason.clone() }
+    }
+
+    /// 状態を遷移する（理由は失敗・縮退時に記録する）
+    pub fn transition(&self, next: LifecycleState, reason: Option<String>) -> Result<(), InvalidTransition> {
+        let mut current_reason = self.reason.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let current = self.state();
+        if !current.can_transition_to(next) {
+            return Err(InvalidTransition { from: current, to: next });
+        }
+
+        // 終了しても失敗・縮退の理由は残す
+        if next != LifecycleState::ShutDown {
+            *current_reason = reason;
+        }
+        self.state.store(next as u8, Ordering::Release);
+
+        let status = LifecycleStatus { state: next, reason: current_reason.clone() };
+        match next {
+            LifecycleState::Failed | LifecycleState::Degraded => {
+                logger::error(&format!("state: {} -> {}", current, status))
+            }
+            _ => logger::info(&format!("state: {} -> {}", current, status)),
+        }
+        Ok(())
+    }
+
+    /// 初期化に失敗した
+    pub fn fail(&self, error: &StartupError) -> Result<(), InvalidTransition> {
+        self.transition(LifecycleState::Failed, Some(error.to_string()))
+    }
+
+    /// 動作中に異常を検出してフックを無効化した
+    pub fn degrade(&self, reason: String) -> Result<(), InvalidTransition> {
+        self.transition(LifecycleState::Degraded, Some(reason))
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Mod全体の状態
+pub static LIFECYCLE: Lifecycle = Lifecycle::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LifecycleState::*;
+
+    const ALL: [LifecycleState; 6] = [Uninitialized, Scanning, Hooked, Degraded, Failed, ShutDown];
+
+    #[test]
+    fn allowed_transitions() {
+        let allowed = [
+            (Uninitialized, Scanning),
+            (Uninitialized, Failed),
+            (Scanning, Hooked),
+            (Scanning, Failed),
+            (Hooked, Degraded),
+            (Uninitialized, ShutDown),
+            (Scanning, ShutDown),
+            (Hooked, ShutDown),
+            (Degraded, ShutDown),
+            (Failed, ShutDown),
+        ];
+        for from in ALL {
+            for to in ALL {
+                assert_eq!(from.can_transition_to(to), allowed.contains(&(from, to)), "{} -> {}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_leaving_terminal_states() {
+        let lifecycle = Lifecycle::new();
+        lifecycle.transition(Scanning, None).unwrap();
+        lifecycle.fail(&StartupError::TargetNotFound("render_level")).unwrap();
+        assert_eq!(
+            lifecycle.transition(Hooked, None),
+            Err(InvalidTransition { from: Failed, to: Hooked })
+        );
+
+        lifecycle.transition(ShutDown, None).unwrap();
+        assert_eq!(
+            lifecycle.transition(Hooked, None),
+            Err(InvalidTransition { from: ShutDown, to: Hooked })
+        );
+        // 2回目の終了も拒否する（shutdown を1回だけ実行するため）
+        assert!(lifecycle.transition(ShutDown, None).is_err());
+        assert_eq!(lifecycle.state(), ShutDown);
+    }
+
+    #[test]
+    fn status_keeps_the_reason_after_shutdown() {
+        let lifecycle = Lifecycle::new();
+        assert_eq!(lifecycle.status().to_string(), "uninitialized");
+
+        lifecycle.transition(Scanning, None).unwrap();
+        lifecycle.transition(Hooked, None).unwrap();
+        assert_eq!(lifecycle.status().to_string(), "hooked");
+
+        lifecycle.degrade("implausible FOV values".to_string()).unwrap();
+        assert_eq!(lifecycle.status().to_string(), "degraded (implausible FOV values)");
+
+        lifecycle.transition(ShutDown, None).unwrap();
+        let status = lifecycle.status();
+        assert_eq!(status.state, ShutDown);
+        assert_eq!(status.to_string(), "shut down (implausible FOV values)");
+    }
+
+    #[test]
+    fn failure_reason_is_the_error_text() {
+        let lifecycle = Lifecycle::new();
+        lifecycle.transition(Scanning, None).unwrap();
+        lifecycle.fail(&StartupError::Cancelled).unwrap();
+        assert_eq!(lifecycle.status().to_string(), "failed (shut down during startup)");
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for (code, state) in ALL.into_iter().enumerate() {
+            assert_eq!(state.code(), code as u8);
+            assert_eq!(LifecycleState::from_code(state.code()), state);
+        }
+        assert_eq!(LifecycleState::from_code(200), Uninitialized);
+    }
+}
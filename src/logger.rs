@@ -0,0 +1,51 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::paths;
+
+// 複数スレッドからの書き込みが混ざらないようにするためのロック
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// ログファイルのパスを取得 (mods/Zoom/zoom.log)
+pub fn log_path() -> PathBuf {
+    paths::mod_file("zoom.log")
+}
+
+/// ログファイルを初期化（前回起動時の内容を破棄）
+pub fn init() {
+    let _guard = LOG_LOCK.lock();
+    let _ = fs::write(log_path(), "");
+}
+
+pub fn info(message: &str) {
+    write("INFO", message);
+}
+
+pub fn warn(message: &str) {
+    write("WARN", message);
+}
+
+pub fn error(message: &str) {
+    write("ERROR", message);
+}
+
+fn write(level: &str, message: &str) {
+    let _guard = LOG_LOCK.lock();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(log_path()) {
+        let _ = writeln!(
+            file,
+            "[{}.{:03}] [{}] {}",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            level,
+            message
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+/// 実行時のオフセットで指定する構造体のフィールド
+pub struct Field<T> {
+    offset: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+// T が Copy でなくてもオフセットとしてコピーできるように手動で実装
+impl<T> Clone for Field<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Field<T> {}
+
+impl<T> fmt::Debug for Field<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Field(0x{:X})", self.offset)
+    }
+}
+
+impl<T> Field<T> {
+    pub const fn new(offset: usize) -> Self {
+        Self { offset, _marker: PhantomData }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// フィールドの終端（オフセット + 型のサイズ）
+    pub fn end(&self) -> usize {
+        self.offset + size_of::<T>()
+    }
+}
+
+/// メモリアクセスのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    /// オブジェクトのポインタが null
+    NullPointer,
+    /// フィールドがオブジェクトの範囲外
+    OutOfBounds { offset: usize, size: usize, object_size: usize },
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NullPointer => write!(f, "null object pointer"),
+            Self::OutOfBounds { offset, size, object_size } => write!(
+                f,
+                "field at 0x{:X} ({} bytes) is outside the object (0x{:X} bytes)",
+                offset, size, object_size
+            ),
+        }
+    }
+}
+
+/// サイズの分かっているメモリ上のオブジェクト
+///
+/// ゲームのオブジェクトとバイト列のバッファを同じように扱い、
+/// フィールドへのアクセスごとに範囲を検査する。
+#[derive(Clone, Copy, Debug)]
+pub struct ObjectRef<'a> {
+    base: NonNull<u8>,
+    size: usize,
+    _marker: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> ObjectRef<'a> {
+    /// ポインタからオブジェクトを作成
+    ///
+    /// # Safety
+    /// `base` が null でない場合、`base` から `size` バイトが `'a` の間読み書き可能であること。
+    pub unsafe fn from_raw(base: *mut u8, size: usize) -> Result<Self, MemoryError> {
+        let base = NonNull::new(base).ok_or(MemoryError::NullPointer)?;
+        Ok(Self { base, size, _marker: PhantomData })
+    }
+
+    /// バイト列のバッファをオブジェクトとして扱う
+    pub fn from_slice(buffer: &'a mut [u8]) -> Self {
+        let size = buffer.len();
+        Self {
+            base: NonNull::new(buffer.as_mut_ptr()).unwrap_or(NonNull::dangling()),
+            size,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn address(&self) -> usize {
+        self.base.as_ptr() as usize
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// フィールドがオブジェクトの範囲内か検査
+    pub fn check<T>(&self, field: Field<T>) -> Result<(), MemoryError> {
+        if field.end() > self.size {
+            return Err(MemoryError::OutOfBounds {
+                offset: field.offset(),
+                size: size_of::<T>(),
+                object_size: self.size,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn read<T: Copy>(&self, field: Field<T>) -> Result<T, MemoryError> {
+        self.check(field)?;
+        // 範囲は検査済み。ゲームの構造体はアラインされていない可能性もあるため unaligned で読む
+        Ok(unsafe { (self.base.as_ptr().add(field.offset()) as *const T).read_unaligned() })
+    }
+
+    pub fn write<T: Copy>(&self, field: Field<T>, value: T) -> Result<(), MemoryError> {
+        self.check(field)?;
+        unsafe { (self.base.as_ptr().add(field.offset()) as *mut T).write_unaligned(value) };
+        Ok(())
+    }
+
+    /// ポインタのフィールドを辿り、指す先を `size` バイトのオブジェクトとして返す
+    ///
+    /// # Safety
+    /// フィールドの値が null でない場合、指す先の `size` バイトが `'a` の間読み書き可能であること。
+    pub unsafe fn follow(&self, field: Field<usize>, size: usize) -> Result<ObjectRef<'a>, MemoryError> {
+        let pointer = self.read(field)?;
+        ObjectRef::from_raw(pointer as *mut u8, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_writes_unaligned_fields() {
+        let mut buffer = [0u8; 16];
+        let object = ObjectRef::from_slice(&mut buffer);
+        let field = Field::<u32>::new(3);
+        object.write(field, 0xDEAD_BEEF).unwrap();
+        assert_eq!(object.read(field), Ok(0xDEAD_BEEF));
+        assert_eq!(object.size(), 16);
+        assert_eq!(&buffer[3..7], &0xDEAD_BEEFu32.to_le_bytes());
+    }
+
+    #[test]
+    fn rejects_fields_past_the_end() {
+        let mut buffer = [0u8; 16];
+        let object = ObjectRef::from_slice(&mut buffer);
+        let field = Field::<u64>::new(12);
+        let error = MemoryError::OutOfBounds { offset: 12, size: 8, object_size: 16 };
+        assert_eq!(object.check(field), Err(error));
+        assert_eq!(object.read(field), Err(error));
+        assert_eq!(object.write(field, 1), Err(error));
+        assert_eq!(object.check(Field::<u64>::new(8)), Ok(()));
+    }
+
+    #[test]
+    fn follows_pointers() {
+        let mut target = [7u8; 4];
+        let mut buffer = [0u8; 16];
+        buffer[8..16].copy_from_slice(&(target.as_mut_ptr() as usize).to_le_bytes());
+        let object = ObjectRef::from_slice(&mut buffer);
+
+        let followed = unsafe { object.follow(Field::new(8), target.len()) }.unwrap();
+        assert_eq!(followed.address(), target.as_ptr() as usize);
+        assert_eq!(followed.read(Field::<u8>::new(3)), Ok(7));
+        assert!(followed.read(Field::<u8>::new(4)).is_err());
+
+        let null = unsafe { object.follow(Field::new(0), 4) };
+        assert_eq!(null.err().map(|error| error.to_string()), Some("null object pointer".to_string()));
+    }
+
+    #[test]
+    fn rejects_null_objects() {
+        let object = unsafe { ObjectRef::from_raw(std::ptr::null_mut(), 16) };
+        assert!(matches!(object, Err(MemoryError::NullPointer)));
+    }
+}
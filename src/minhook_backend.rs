@@ -0,0 +1,70 @@
+use std::ffi::c_void;
+use std::ptr::null_mut;
+
+use minhook_sys::*;
+
+use crate::hook_backend::{HookBackend, HookError};
+
+/// MinHook によるフック
+pub struct MinHookBackend;
+
+impl MinHookBackend {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for MinHookBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// MH_STATUS をエラーに変換（値は MinHook.h の定義）
+fn check(status: MH_STATUS) -> Result<(), HookError> {
+    let code = status;
+    if code == MH_OK {
+        return Ok(());
+    }
+    Err(match code {
+        1 => HookError::AlreadyInitialized,
+        2 => HookError::NotInitialized,
+        3 => HookError::AlreadyCreated,
+        4 => HookError::NotCreated,
+        5 => HookError::AlreadyEnabled,
+        6 => HookError::AlreadyDisabled,
+        7 => HookError::NotExecutable,
+        8 => HookError::UnsupportedFunction,
+        9 => HookError::MemoryAlloc,
+        10 => HookError::MemoryProtect,
+        _ => HookError::Unknown(code),
+    })
+}
+
+impl HookBackend for MinHookBackend {
+    fn initialize(&self) -> Result<(), HookError> {
+        check(unsafe { MH_Initialize() })
+    }
+
+    fn uninitialize(&self) -> Result<(), HookError> {
+        check(unsafe { MH_Uninitialize() })
+    }
+
+    unsafe fn create(&self, target: usize, detour: usize) -> Result<usize, HookError> {
+        let mut original: *mut c_void = null_mut();
+        check(MH_CreateHook(target as *mut c_void, detour as *mut c_void, &mut original))?;
+        Ok(original as usize)
+    }
+
+    unsafe fn enable(&self, target: usize) -> Result<(), HookError> {
+        check(MH_EnableHook(target as *mut c_void))
+    }
+
+    unsafe fn disable(&self, target: usize) -> Result<(), HookError> {
+        check(MH_DisableHook(target as *mut c_void))
+    }
+
+    unsafe fn remove(&self, target: usize) -> Result<(), HookError> {
+        check(MH_RemoveHook(target as *mut c_void))
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::hook_backend::{HookBackend, HookError};
+
+#[derive(Clone, Copy, Debug)]
+struct MockHook {
+    detour: usize,
+    enabled: bool,
+}
+
+struct MockState {
+    initialized: bool,
+    hooks: BTreeMap<usize, MockHook>,
+    /// 次の create で返すエラー
+    fail_create: Option<HookError>,
+    /// 次の enable で返すエラー
+    fail_enable: Option<HookError>,
+}
+
+/// メモリを書き換えずにフックの状態だけを記録するバックエンド
+///
+/// MinHook と同じ条件で同じエラーを返す。トランポリンには対象のアドレスをそのまま返す。
+pub struct MockHookBackend {
+    state: Mutex<MockState>,
+}
+
+impl MockHookBackend {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(MockState {
+                initialized: false,
+                hooks: BTreeMap::new(),
+                fail_create: None,
+                fail_enable: None,
+            }),
+        }
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut MockState) -> R) -> R {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut state)
+    }
+
+    /// 次の create を失敗させる
+    pub fn fail_next_create(&self, error: HookError) {
+        self.with_state(|state| state.fail_create = Some(error));
+    }
+
+    /// 次の enable を失敗させる
+    pub fn fail_next_enable(&self, error: HookError) {
+        self.with_state(|state| state.fail_enable = Some(error));
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.with_state(|state| state.initialized)
+    }
+
+    /// 作成済みのフックの数
+    pub fn hook_count(&self) -> usize {
+        self.with_state(|state| state.hooks.len())
+    }
+
+    /// フックが有効か（作成されていなければ None）
+    pub fn is_enabled(&self, target: usize) -> Option<bool> {
+        self.with_state(|state| state.hooks.get(&target).map(|hook| hook.enabled))
+    }
+
+    /// フックのデトゥア（作成されていなければ None）
+    pub fn detour_of(&self, target: usize) -> Option<usize> {
+        self.with_state(|state| state.hooks.get(&target).map(|hook| hook.detour))
+    }
+}
+
+impl Default for MockHookBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HookBackend for MockHookBackend {
+    fn initialize(&self) -> Result<(), HookError> {
+        self.with_state(|state| {
+            if state.initialized {
+                return Err(HookError::AlreadyInitialized);
+            }
+            state.initialized = true;
+            Ok(())
+        })
+    }
+
+    fn uninitialize(&self) -> Result<(), HookError> {
+        self.with_state(|state| {
+            if !state.initialized {
+                return Err(HookError::NotInitialized);
+            }
+            // MinHook と同じく残っているフックはすべて削除される
+            state.hooks.clear();
+            state.initialized = false;
+            Ok(())
+        })
+    }
+
+    unsafe fn create(&self, target: usize, detour: usize) -> Result<usize, HookError> {
+        self.with_state(|state| {
+            if !state.initialized {
+                return Err(HookError::NotInitialized);
+            }
+            if let Some(error) = state.fail_create.take() {
+                return Err(error);
+            }
+            if target == 0 || detour == 0 {
+                return Err(HookError::NotExecutable);
+            }
+            if state.hooks.contains_key(&target) {
+                return Err(HookError::AlreadyCreated);
+            }
+            state.hooks.insert(target, MockHook { detour, enabled: false });
+            Ok(target)
+        })
+    }
+
+    unsafe fn enable(&self, target: usize) -> Result<(), HookError> {
+        self.with_state(|state| {
+            if !state.initialized {
+                return Err(HookError::NotInitialized);
+            }
+            if let Some(error) = state.fail_enable.take() {
+                return Err(error);
+            }
+            let hook = state.hooks.get_mut(&target).ok_or(HookError::NotCreated)?;
+            if hook.enabled {
+                return Err(HookError::AlreadyEnabled);
+            }
+            hook.enabled = true;
+            Ok(())
+        })
+    }
+
+    unsafe fn disable(&self, target: usize) -> Result<(), HookError> {
+        self.with_state(|state| {
+            if !state.initialized {
+                return Err(HookError::NotInitialized);
+            }
+            let hook = state.hooks.get_mut(&target).ok_or(HookError::NotCreated)?;
+            if !hook.enabled {
+                return Err(HookError::AlreadyDisabled);
+            }
+            hook.enabled = false;
+            Ok(())
+        })
+    }
+
+    unsafe fn remove(&self, target: usize) -> Result<(), HookError> {
+        self.with_state(|state| {
+            if !state.initialized {
+                return Err(HookError::NotInitialized);
+            }
+            state.hooks.remove(&target).map(|_| ()).ok_or(HookError::NotCreated)
+        })
+    }
+}
@@ -0,0 +1,396 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use serde::Deserialize;
+
+use crate::hook_target::HookTarget;
+use crate::logger;
+use crate::paths;
+use crate::signature::{Signature, SignatureError};
+use crate::version::GameVersion;
+
+const RENDER_LEVEL_SIG: &[u8] = &[
+    0x48, 0x8B, 0xC4, 0x48, 0x89, 0x58, 0x00, 0x55, 0x56, 0x57, 0x41, 0x54, 0x41, 0x55, 0x41, 0x56, 0x41, 0x57, 0x48, 0x8D, 0xA8, 0x00, 0x00, 0x00, 0x00, 0x48, 0x81, 0xEC, 0x00, 0x00, 0x00, 0x00, 0x0F, 0x29, 0x70, 0x00, 0x0F, 0x29, 0x78, 0x00, 0x44, 0x0F, 0x29, 0x40, 0x00, 0x44, 0x0F, 0x29, 0x48, 0x00, 0x48, 0x8B, 0x05, 0x00, 0x00, 0x00, 0x00, 0x48, 0x33, 0xC4, 0x48, 0x89, 0x85, 0x00, 0x00, 0x00, 0x00, 0x4D, 0x8B, 0xE8, 0x4C, 0x8B, 0xE2, 0x4C, 0x8B, 0xF9
+];
+
+const RENDER_LEVEL_MASK: &[u8] = &[
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF
+];
+
+/// ゲームバージョンごとの組み込みオフセット
+struct BuiltinOffsets {
+    version: GameVersion,
+    /// renderLevel のシグネチャ候補 (パターン, マスク)。先頭から順に試す
+    render_level_signatures: &'static [(&'static [u8], &'static [u8])],
+    level_renderer_player: usize,
+    fov_x: usize,
+    fov_y: usize,
+    level_renderer_size: Option<usize>,
+    player_size: Option<usize>,
+}
+
+/// 対応済みのゲームバージョン（新しいものを末尾に追加する）
+const BUILTIN_OFFSETS: &[BuiltinOffsets] = &[BuiltinOffsets {
+    version: GameVersion::new(1, 21, 124, 2),
+    render_level_signatures: &[(RENDER_LEVEL_SIG, RENDER_LEVEL_MASK)],
+    level_renderer_player: 0x3F0,
+    fov_x: 0xF80,
+    fov_y: 0xF94,
+    // クラスの大きさは確認できていないため、フィールドの位置だけを検査する
+    level_renderer_size: None,
+    player_size: None,
+}];
+
+/// オフセットとして許容する最大値（これを超える値は設定ミスとみなす）
+const MAX_FIELD_OFFSET: u64 = 0x10000;
+
+/// フック対象の関数シグネチャと構造体フィールドのオフセット
+#[derive(Clone, Debug)]
+pub struct GameOffsets {
+    /// LevelRenderer::renderLevel のシグネチャ候補
+    pub render_level: HookTarget,
+    /// LevelRenderer 内の LevelRendererPlayer ポインタのオフセット
+    pub level_renderer_player: usize,
+    /// LevelRendererPlayer 内の水平FOVのオフセット
+    pub fov_x: usize,
+    /// LevelRendererPlayer 内の垂直FOVのオフセット
+    pub fov_y: usize,
+    /// LevelRenderer のサイズ（分かっていればフィールドがこの範囲内にあるか検査する）
+    pub level_renderer_size: Option<usize>,
+    /// LevelRendererPlayer のサイズ
+    pub player_size: Option<usize>,
+}
+
+impl Default for GameOffsets {
+    /// 最新の対応バージョンの組み込みオフセット
+    fn default() -> Self {
+        Self::from_builtin(&BUILTIN_OFFSETS[BUILTIN_OFFSETS.len() - 1])
+    }
+}
+
+impl GameOffsets {
+    fn from_builtin(builtin: &BuiltinOffsets) -> Self {
+        Self {
+            render_level: HookTarget::new(
+                "render_level",
+                builtin
+                    .render_level_signatures
+                    .iter()
+                    .map(|(bytes, mask)| {
+                        Signature::from_bytes_and_mask(bytes, mask)
+                            .expect("built-in render level signature is valid")
+                    })
+                    .collect(),
+            ),
+            level_renderer_player: builtin.level_renderer_player,
+            fov_x: builtin.fov_x,
+            fov_y: builtin.fov_y,
+            level_renderer_size: builtin.level_renderer_size,
+            player_size: builtin.player_size,
+        }
+    }
+
+    /// ゲームバージョンに対応する組み込みオフセットを取得
+    ///
+    /// 対応表に無いバージョンの場合は最新の対応バージョンのものを使用する。
+    pub fn builtin_for(version: Option<GameVersion>) -> Self {
+        let Some(version) = version else {
+            logger::warn("game version unknown, using offsets for the latest supported version");
+            return Self::default();
+        };
+
+        match BUILTIN_OFFSETS.iter().find(|builtin| builtin.version.same_release(&version)) {
+            Some(builtin) => {
+                logger::info(&format!("using built-in offsets for {}", builtin.version));
+                Self::from_builtin(builtin)
+            }
+            None => {
+                logger::warn(&format!(
+                    "game version {} is not supported, trying offsets for {}",
+                    version,
+                    BUILTIN_OFFSETS[BUILTIN_OFFSETS.len() - 1].version
+                ));
+                Self::default()
+            }
+        }
+    }
+}
+
+
+/// offsets.yml 用の構造体（指定されたフィールドのみ上書きする）
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct OffsetsYaml {
+    /// 上書きを適用するゲームバージョン（省略時は常に適用）
+    game_version: Option<String>,
+    /// 単一のシグネチャ（render_level_signatures の省略形）
+    render_level_signature: Option<String>,
+    /// 組み込みのシグネチャより先に試すシグネチャ候補
+    render_level_signatures: Option<Vec<String>>,
+    level_renderer_player: Option<u64>,
+    fov_x: Option<u64>,
+    fov_y: Option<u64>,
+    level_renderer_size: Option<u64>,
+    player_size: Option<u64>,
+}
+
+/// offsets.yml の検証エラー
+#[derive(Debug)]
+pub enum OffsetsError {
+    /// YAMLとして解析できない、または未知のフィールドがある
+    Parse(serde_yaml::Error),
+    /// シグネチャ文字列が不正 (index は0始まり)
+    Signature { index: usize, error: SignatureError },
+    /// オフセットが許容範囲外
+    OutOfRange { field: &'static str, value: u64 },
+    /// オフセットがフィールドの型に対してアラインされていない
+    Misaligned { field: &'static str, value: u64, align: u64 },
+    /// fov_x と fov_y が同じ位置を指している
+    OverlappingFov,
+    /// フィールドがオブジェクトのサイズを超えている
+    OutsideObject { field: &'static str, end: usize, object_size: usize },
+    /// game_version を解析できない
+    InvalidGameVersion(String),
+    /// game_version が実行中のゲームと一致しない
+    VersionMismatch { expected: GameVersion, actual: GameVersion },
+}
+
+impl fmt::Display for OffsetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "invalid offsets file: {}", error),
+            Self::Signature { index, error } => {
+                write!(f, "render level signature #{}: {}", index + 1, error)
+            }
+            Self::OutOfRange { field, value } => {
+                write!(f, "{}: 0x{:X} exceeds the maximum of 0x{:X}", field, value, MAX_FIELD_OFFSET)
+            }
+            Self::Misaligned { field, value, align } => {
+                write!(f, "{}: 0x{:X} is not aligned to {} bytes", field, value, align)
+            }
+            Self::OverlappingFov => write!(f, "fov_x and fov_y must not point to the same field"),
+            Self::OutsideObject { field, end, object_size } => write!(
+                f,
+                "{}: field ends at 0x{:X}, outside the object (0x{:X} bytes)",
+                field, end, object_size
+            ),
+            Self::InvalidGameVersion(text) => write!(f, "game_version: invalid version '{}'", text),
+            Self::VersionMismatch { expected, actual } => {
+                write!(f, "written for game version {}, but running {}", expected, actual)
+            }
+        }
+    }
+}
+
+/// オフセット上書きファイルのパスを取得 (mods/Zoom/offsets.yml)
+pub fn offsets_path() -> PathBuf {
+    paths::mod_file("offsets.yml")
+}
+
+/// ゲームバージョンに対応する組み込みのオフセットに offsets.yml の内容を適用して読み込む
+///
+/// ファイルが不正な場合は一切適用せず、組み込みのオフセットを使用する。
+pub fn load(version: Option<GameVersion>) -> GameOffsets {
+    let builtin = GameOffsets::builtin_for(version);
+    let path = offsets_path();
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => {
+            logger::info("offsets.yml not found, using built-in offsets");
+            log_offsets(&builtin);
+            return builtin;
+        }
+    };
+
+    match apply_overrides(&content, builtin.clone(), version) {
+        Ok((offsets, overridden)) => {
+            if overridden.is_empty() {
+                logger::info("offsets.yml contains no overrides, using built-in offsets");
+            }
+            for field in overridden {
+                logger::info(&format!("offsets.yml overrides {}", field));
+            }
+            log_offsets(&offsets);
+            offsets
+        }
+        Err(error) => {
+            logger::error(&format!(
+                "{} is rejected ({}), using built-in offsets",
+                path.display(),
+                error
+            ));
+            log_offsets(&builtin);
+            builtin
+        }
+    }
+}
+
+/// YAML文字列を検証し、指定されたフィールドを上書きする
+///
+/// 戻り値の2番目は上書きされたフィールド名の一覧。
+pub fn apply_overrides(
+    content: &str,
+    mut offsets: GameOffsets,
+    version: Option<GameVersion>,
+) -> Result<(GameOffsets, Vec<&'static str>), OffsetsError> {
+    // 空ファイルやコメントのみのファイルは上書きなしとして扱う
+    let yaml = match serde_yaml::from_str::<Option<OffsetsYaml>>(content) {
+        Ok(yaml) => yaml.unwrap_or_default(),
+        Err(error) => return Err(OffsetsError::Parse(error)),
+    };
+    let mut overridden = Vec::new();
+
+    if let Some(text) = yaml.game_version {
+        let expected = text
+            .parse::<GameVersion>()
+            .map_err(|_| OffsetsError::InvalidGameVersion(text.clone()))?;
+        if let Some(actual) = version {
+            if !expected.same_release(&actual) {
+                return Err(OffsetsError::VersionMismatch { expected, actual });
+            }
+        }
+    }
+
+    // 追加のシグネチャは組み込みのものより優先して試す
+    let extra_signatures: Vec<String> = yaml
+        .render_level_signature
+        .into_iter()
+        .chain(yaml.render_level_signatures.unwrap_or_default())
+        .collect();
+    if !extra_signatures.is_empty() {
+        let mut signatures = extra_signatures
+            .iter()
+            .enumerate()
+            .map(|(index, text)| {
+                Signature::parse(text).map_err(|error| OffsetsError::Signature { index, error })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        signatures.append(&mut offsets.render_level.signatures);
+        offsets.render_level.signatures = signatures;
+        overridden.push("render_level_signatures");
+    }
+    if let Some(value) = yaml.level_renderer_player {
+        offsets.level_renderer_player = validate_offset("level_renderer_player", value, 8)?;
+        overridden.push("level_renderer_player");
+    }
+    if let Some(value) = yaml.fov_x {
+        offsets.fov_x = validate_offset("fov_x", value, 4)?;
+        overridden.push("fov_x");
+    }
+    if let Some(value) = yaml.fov_y {
+        offsets.fov_y = validate_offset("fov_y", value, 4)?;
+        overridden.push("fov_y");
+    }
+
+    if let Some(value) = yaml.level_renderer_size {
+        offsets.level_renderer_size = Some(validate_offset("level_renderer_size", value, 8)?);
+        overridden.push("level_renderer_size");
+    }
+    if let Some(value) = yaml.player_size {
+        offsets.player_size = Some(validate_offset("player_size", value, 8)?);
+        overridden.push("player_size");
+    }
+
+    if offsets.fov_x == offsets.fov_y {
+        return Err(OffsetsError::OverlappingFov);
+    }
+    check_inside("level_renderer_player", offsets.level_renderer_player + 8, offsets.level_renderer_size)?;
+    check_inside("fov_x", offsets.fov_x + 4, offsets.player_size)?;
+    check_inside("fov_y", offsets.fov_y + 4, offsets.player_size)?;
+
+    Ok((offsets, overridden))
+}
+
+fn validate_offset(field: &'static str, value: u64, align: u64) -> Result<usize, OffsetsError> {
+    if value > MAX_FIELD_OFFSET {
+        return Err(OffsetsError::OutOfRange { field, value });
+    }
+    if !value.is_multiple_of(align) {
+        return Err(OffsetsError::Misaligned { field, value, align });
+    }
+    Ok(value as usize)
+}
+
+/// フィールドの終端がオブジェクトのサイズ内か検査（サイズが分からなければ検査しない）
+fn check_inside(field: &'static str, end: usize, object_size: Option<usize>) -> Result<(), OffsetsError> {
+    match object_size {
+        Some(object_size) if end > object_size => Err(OffsetsError::OutsideObject { field, end, object_size }),
+        _ => Ok(()),
+    }
+}
+
+fn format_size(size: Option<usize>) -> String {
+    size.map_or_else(|| "unknown".to_string(), |size| format!("0x{:X}", size))
+}
+
+fn log_offsets(offsets: &GameOffsets) {
+    logger::info(&format!(
+        "offsets in use: level_renderer_player=0x{:X} fov_x=0x{:X} fov_y=0x{:X} \
+         level_renderer_size={} player_size={}",
+        offsets.level_renderer_player,
+        offsets.fov_x,
+        offsets.fov_y,
+        format_size(offsets.level_renderer_size),
+        format_size(offsets.player_size)
+    ));
+    for (index, signature) in offsets.render_level.signatures.iter().enumerate() {
+        logger::info(&format!("render level signature #{}: {}", index + 1, signature));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_offsets_load_without_overrides() {
+        let (offsets, overridden) = apply_overrides("", GameOffsets::default(), None).unwrap();
+        assert!(overridden.is_empty());
+        assert_eq!(offsets.level_renderer_size, None);
+        assert_eq!(offsets.player_size, None);
+    }
+
+    #[test]
+    fn field_override_alone_loads_without_object_sizes() {
+        let content = "fov_x: 0x1080\nfov_y: 0x1094\n";
+        let (offsets, overridden) = apply_overrides(content, GameOffsets::default(), None).unwrap();
+        assert_eq!((offsets.fov_x, offsets.fov_y), (0x1080, 0x1094));
+        assert_eq!(offsets.player_size, None);
+        assert_eq!(overridden, ["fov_x", "fov_y"]);
+
+        // サイズが分からなくても最大値の検査は行う
+        let error = apply_overrides("fov_x: 0x10004\n", GameOffsets::default(), None).err();
+        assert!(matches!(error, Some(OffsetsError::OutOfRange { field: "fov_x", .. })));
+    }
+
+    #[test]
+    fn overrides_object_sizes() {
+        let content = "fov_x: 0x100\nfov_y: 0x104\nplayer_size: 0x108\n";
+        let (offsets, overridden) = apply_overrides(content, GameOffsets::default(), None).unwrap();
+        assert_eq!(offsets.player_size, Some(0x108));
+        assert_eq!(overridden, ["fov_x", "fov_y", "player_size"]);
+    }
+
+    #[test]
+    fn rejects_fields_outside_the_object() {
+        let error = apply_overrides("player_size: 0x100\n", GameOffsets::default(), None).err();
+        assert!(matches!(
+            error,
+            Some(OffsetsError::OutsideObject { field: "fov_x", end: 0xF84, object_size: 0x100 })
+        ));
+
+        let content = "level_renderer_player: 0x2000\nlevel_renderer_size: 0x1000\n";
+        let error = apply_overrides(content, GameOffsets::default(), None).err();
+        assert!(matches!(error, Some(OffsetsError::OutsideObject { field: "level_renderer_player", .. })));
+    }
+
+    #[test]
+    fn rejects_invalid_object_sizes() {
+        let error = apply_overrides("player_size: 0x20000\n", GameOffsets::default(), None).err();
+        assert!(matches!(error, Some(OffsetsError::OutOfRange { field: "player_size", .. })));
+
+        let error = apply_overrides("level_renderer_size: 0x1001\n", GameOffsets::default(), None).err();
+        assert!(matches!(error, Some(OffsetsError::Misaligned { field: "level_renderer_size", .. })));
+    }
+}
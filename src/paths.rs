@@ -0,0 +1,24 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Modのディレクトリを取得 (mods/Zoom)
+pub fn mod_directory() -> PathBuf {
+    let mut path = std::env::current_exe().unwrap_or_default();
+    path.pop(); // 実行ファイルのディレクトリ
+    path.push("mods");
+    path.push("Zoom");
+
+    // ディレクトリが存在しない場合は作成
+    if !path.exists() {
+        let _ = fs::create_dir_all(&path);
+    }
+
+    path
+}
+
+/// Modディレクトリ内のファイルパスを取得
+pub fn mod_file(name: &str) -> PathBuf {
+    let mut path = mod_directory();
+    path.push(name);
+    path
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+/// 関数の先頭に書き込まれたジャンプの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JumpKind {
+    /// EB rel8
+    Rel8,
+    /// E9 rel32
+    Rel32,
+    /// FF 25 disp32 (jmp [rip+disp32])
+    RipIndirect,
+    /// 48 B8 imm64 FF E0 (mov rax, imm64; jmp rax)
+    AbsoluteRax,
+}
+
+impl JumpKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rel8 => "jmp rel8",
+            Self::Rel32 => "jmp rel32",
+            Self::RipIndirect => "jmp [rip+disp32]",
+            Self::AbsoluteRax => "mov rax, imm64; jmp rax",
+        }
+    }
+}
+
+/// 他のModなどによって関数の先頭に書き込まれたデトゥア
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ForeignDetour {
+    pub kind: JumpKind,
+    /// ジャンプ先のアドレス（イメージ外のポインタを読む必要がある場合は None）
+    pub destination: Option<usize>,
+}
+
+impl fmt::Display for ForeignDetour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.destination {
+            Some(destination) => write!(f, "{} to 0x{:X}", self.kind.as_str(), destination),
+            None => write!(f, "{}", self.kind.as_str()),
+        }
+    }
+}
+
+/// 他のデトゥアが見つかった場合の動作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForeignDetourPolicy {
+    /// 警告を出してその上からフックする（先にフックしたModも動き続ける）
+    Chain,
+    /// フックせずに諦める
+    Abort,
+}
+
+impl ForeignDetourPolicy {
+    /// 設定値を動作に変換（不明な値は chain）
+    pub fn parse(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "abort" => Self::Abort,
+            _ => Self::Chain,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Chain => "chain",
+            Self::Abort => "abort",
+        }
+    }
+}
+
+fn read_i32(image: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(image.get(offset..offset.checked_add(4)?)?.try_into().ok()?))
+}
+
+fn read_u64(image: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(image.get(offset..offset.checked_add(8)?)?.try_into().ok()?))
+}
+
+/// `rva` の関数の先頭がジャンプに書き換えられていれば、その内容を返す
+pub fn detect_detour(image: &[u8], image_base: usize, rva: usize) -> Option<ForeignDetour> {
+    let code = image.get(rva..)?;
+    let address = image_base.wrapping_add(rva);
+    let relative = |length: usize, displacement: i64| {
+        Some(address.wrapping_add(length).wrapping_add(displacement as usize))
+    };
+
+    let (kind, destination) = match code {
+        [0xEB, rel, ..] => (JumpKind::Rel8, relative(2, *rel as i8 as i64)),
+        [0xE9, ..] => (JumpKind::Rel32, relative(5, read_i32(image, rva + 1)? as i64)),
+        [0xFF, 0x25, ..] => {
+            let disp = read_i32(image, rva + 2)? as i64;
+            // ポインタがイメージ内にあれば読む（MinHook などは直後に置く）
+            let slot = (rva as i64).checked_add(6)?.checked_add(disp)?;
+            let destination = usize::try_from(slot)
+                .ok()
+                .and_then(|slot| read_u64(image, slot))
+                .map(|pointer| pointer as usize);
+            (JumpKind::RipIndirect, destination)
+        }
+        [0x48, 0xB8, ..] if code.get(10..12) == Some(&[0xFF, 0xE0]) => {
+            (JumpKind::AbsoluteRax, Some(read_u64(image, rva + 2)? as usize))
+        }
+        _ => return None,
+    };
+    Some(ForeignDetour { kind, destination })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: usize = 0x1_4000_0000;
+    const RVA: usize = 0x40;
+
+    /// RVA 0x40 の関数の先頭に `code` を置いたイメージ
+    fn image_with(code: &[u8]) -> Vec<u8> {
+        let mut image = vec![0xCC; 0x100];
+        image[RVA..RVA + code.len()].copy_from_slice(code);
+        image
+    }
+
+    fn detour(kind: JumpKind, destination: Option<usize>) -> Option<ForeignDetour> {
+        Some(ForeignDetour { kind, destination })
+    }
+
+    #[test]
+    fn rel8_jump_is_detected_in_both_directions() {
+        let forward = image_with(&[0xEB, 0x10]);
+        assert_eq!(detect_detour(&forward, BASE, RVA), detour(JumpKind::Rel8, Some(BASE + RVA + 2 + 0x10)));
+
+        let backward = image_with(&[0xEB, 0xFE]);
+        assert_eq!(detect_detour(&backward, BASE, RVA), detour(JumpKind::Rel8, Some(BASE + RVA)));
+    }
+
+    #[test]
+    fn rel32_jump_is_detected() {
+        let image = image_with(&[0xE9, 0x00, 0x10, 0x00, 0x00]);
+        assert_eq!(detect_detour(&image, BASE, RVA), detour(JumpKind::Rel32, Some(BASE + RVA + 5 + 0x1000)));
+
+        let backward = image_with(&[0xE9, 0xFB, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(detect_detour(&backward, BASE, RVA), detour(JumpKind::Rel32, Some(BASE + RVA)));
+    }
+
+    #[test]
+    fn rip_indirect_jump_reads_a_pointer_inside_the_image() {
+        // MinHook と同じくポインタを直後に置く
+        let mut code = vec![0xFF, 0x25, 0x00, 0x00, 0x00, 0x00];
+        code.extend_from_slice(&0x7FF8_1234_5678u64.to_le_bytes());
+        let image = image_with(&code);
+        assert_eq!(detect_detour(&image, BASE, RVA), detour(JumpKind::RipIndirect, Some(0x7FF8_1234_5678)));
+    }
+
+    #[test]
+    fn rip_indirect_jump_outside_the_image_has_no_destination() {
+        let after_end = image_with(&[0xFF, 0x25, 0x00, 0x10, 0x00, 0x00]);
+        assert_eq!(detect_detour(&after_end, BASE, RVA), detour(JumpKind::RipIndirect, None));
+
+        let before_start = image_with(&[0xFF, 0x25, 0x00, 0xF0, 0xFF, 0xFF]);
+        assert_eq!(detect_detour(&before_start, BASE, RVA), detour(JumpKind::RipIndirect, None));
+    }
+
+    #[test]
+    fn mov_rax_jmp_rax_is_detected() {
+        let mut code = vec![0x48, 0xB8];
+        code.extend_from_slice(&0x7FF8_0000_1000u64.to_le_bytes());
+        code.extend_from_slice(&[0xFF, 0xE0]);
+        let image = image_with(&code);
+        assert_eq!(detect_detour(&image, BASE, RVA), detour(JumpKind::AbsoluteRax, Some(0x7FF8_0000_1000)));
+    }
+
+    #[test]
+    fn mov_rax_without_jmp_rax_is_not_a_detour() {
+        let mut code = vec![0x48, 0xB8];
+        code.extend_from_slice(&0x7FF8_0000_1000u64.to_le_bytes());
+        code.extend_from_slice(&[0x48, 0x89]);
+        let image = image_with(&code);
+        assert_eq!(detect_detour(&image, BASE, RVA), None);
+    }
+
+    #[test]
+    fn truncated_jump_at_the_end_of_the_image_is_ignored() {
+        for code in [&[0xEB][..], &[0xE9, 0x00, 0x10], &[0xFF, 0x25, 0x00], &[0x48, 0xB8, 0x00, 0x10]] {
+            let mut image = vec![0xCC; 0x40];
+            image.extend_from_slice(code);
+            assert_eq!(detect_detour(&image, BASE, 0x40), None, "{:02X?}", code);
+        }
+    }
+
+    #[test]
+    fn normal_prologue_is_not_a_detour() {
+        let image = image_with(&[0x48, 0x89, 0x5C, 0x24, 0x08, 0x57, 0x48, 0x83, 0xEC, 0x20]);
+        assert_eq!(detect_detour(&image, BASE, RVA), None);
+    }
+
+    #[test]
+    fn rva_past_the_end_of_the_image_is_ignored() {
+        let image = image_with(&[0xE9, 0x00, 0x10, 0x00, 0x00]);
+        assert_eq!(detect_detour(&image, BASE, 0x100), None);
+        assert_eq!(detect_detour(&image, BASE, usize::MAX), None);
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// 再試行の間隔と打ち切るまでの時間
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最初の待ち時間（以後は倍々に延ばす）
+    pub initial_delay: Duration,
+    /// 待ち時間の上限
+    pub max_delay: Duration,
+    /// 待ち時間の合計がこれを超えたら諦める（0 なら再試行しない）
+    pub timeout: Duration,
+}
+
+impl RetryPolicy {
+    pub const DEFAULT_INITIAL_DELAY: Duration = Duration::from_millis(250);
+    pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(4);
+
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            initial_delay: Self::DEFAULT_INITIAL_DELAY,
+            max_delay: Self::DEFAULT_MAX_DELAY,
+            timeout,
+        }
+    }
+
+    pub fn backoff(&self) -> Backoff {
+        Backoff { policy: *self, next: self.initial_delay, waited: Duration::ZERO }
+    }
+}
+
+/// 次の試行までの待ち時間を返すイテレータ（タイムアウトに達すると終わる）
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    policy: RetryPolicy,
+    next: Duration,
+    waited: Duration,
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        if self.waited >= self.policy.timeout {
+            return None;
+        }
+        // 最後の待ち時間はタイムアウトまでに切り詰める
+        let delay = self.next.min(self.policy.timeout - self.waited).max(Duration::from_millis(1));
+        self.waited += delay;
+        self.next = (self.next * 2).min(self.policy.max_delay);
+        Some(delay)
+    }
+}
+
+/// `attempt` が成功するか、再試行できないエラーを返すか、タイムアウトするまで繰り返す
+///
+/// `attempt` には 0 始まりの試行回数を渡す。待つ処理は `sleep` に任せる（テストでは時間を進めない）。
+/// 失敗した場合は最後のエラーを返す。
+pub fn retry<T, E>(
+    policy: &RetryPolicy,
+    mut attempt: impl FnMut(u32) -> Result<T, E>,
+    should_retry: impl Fn(&E) -> bool,
+    mut sleep: impl FnMut(Duration),
+) -> Result<T, E> {
+    let mut backoff = policy.backoff();
+    let mut count = 0;
+    loop {
+        let error = match attempt(count) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        if !should_retry(&error) {
+            return Err(error);
+        }
+        let Some(delay) = backoff.next() else {
+            return Err(error);
+        };
+        sleep(delay);
+        count += 1;
+    }
+}
+
+/// 再試行の待ち時間を途中で打ち切るためのトークン
+pub struct CancelToken {
+    cancelled: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl CancelToken {
+    pub const fn new() -> Self {
+        Self { cancelled: Mutex::new(false), condvar: Condvar::new() }
+    }
+
+    /// 待っているスレッドを起こし、以後の待ちをすぐに終わらせる
+    pub fn cancel(&self) {
+        if let Ok(mut cancelled) = self.cancelled.lock() {
+            *cancelled = true;
+        }
+        self.condvar.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.lock().map_or(true, |cancelled| *cancelled)
+    }
+
+    /// `delay` だけ待つ。待ち終えたら true、取り消されたら false を返す
+    pub fn wait(&self, delay: Duration) -> bool {
+        let deadline = Instant::now() + delay;
+        let Ok(mut cancelled) = self.cancelled.lock() else {
+            return false;
+        };
+        while !*cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            cancelled = match self.condvar.wait_timeout(cancelled, deadline - now) {
+                Ok((guard, _)) => guard,
+                Err(_) => return false,
+            };
+        }
+        false
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn wait_completes_without_cancel() {
+        let token = CancelToken::new();
+        assert!(token.wait(Duration::from_millis(5)));
+        assert!(!token.is_cancelled());
+    }
+
+    #[test]
+    fn cancel_wakes_a_waiting_thread() {
+        let token = Arc::new(CancelToken::new());
+        let waiter = {
+            let token = Arc::clone(&token);
+            thread::spawn(move || {
+                let started = Instant::now();
+                (token.wait(Duration::from_secs(30)), started.elapsed())
+            })
+        };
+        thread::sleep(Duration::from_millis(20));
+        token.cancel();
+        let (completed, elapsed) = waiter.join().unwrap();
+        assert!(!completed);
+        assert!(elapsed < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn wait_after_cancel_returns_immediately() {
+        let token = CancelToken::new();
+        token.cancel();
+        assert!(token.is_cancelled());
+        assert!(!token.wait(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn retry_stops_when_sleep_is_cancelled() {
+        let token = CancelToken::new();
+        token.cancel();
+        let policy = RetryPolicy::with_timeout(Duration::from_secs(30));
+        let mut attempts = 0;
+        let result: Result<(), &str> = retry(
+            &policy,
+            |_| {
+                attempts += 1;
+                if token.is_cancelled() && attempts > 1 {
+                    Err("cancelled")
+                } else {
+                    Err("not found")
+                }
+            },
+            |error| *error == "not found",
+            |delay| {
+                token.wait(delay);
+            },
+        );
+        assert_eq!(result, Err("cancelled"));
+        assert_eq!(attempts, 2);
+    }
+}
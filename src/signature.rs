@@ -0,0 +1,320 @@
+use std::fmt;
+
+/// バイトパターン（ワイルドカード付き）によるシグネチャ
+///
+/// パターンの後に `|` で区切って解決ステップを書くと、一致位置から実際の関数を辿れる。
+/// 例: `"E8 ?? ?? ?? ?? 48 8B D8 | offset 1, rel32"` (call 命令の呼び出し先)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// None はワイルドカード（任意のバイトに一致）
+    pattern: Vec<Option<u8>>,
+    /// 一致位置から対象アドレスを求める手順（空なら一致位置そのもの）
+    steps: Vec<ResolveStep>,
+}
+
+/// 一致位置から対象アドレスを求める手順の1ステップ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolveStep {
+    /// 現在位置を指定バイト数だけ移動 (`offset 3`, `offset -0x10`)
+    Offset(isize),
+    /// 現在位置の符号付き32bit相対値を読み、次の命令位置 (+4) からの相対アドレスとして辿る (`rel32`)
+    Rel32,
+    /// 現在位置の64bitポインタを読み、それが指すアドレスに移動 (`deref`)
+    Deref,
+}
+
+/// シグネチャ文字列の解析エラー
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    /// パターンが空
+    Empty,
+    /// 16進数でもワイルドカードでもないトークン
+    InvalidToken(String),
+    /// パターンとマスクの長さが一致しない
+    LengthMismatch,
+    /// ワイルドカードのみで構成されている
+    OnlyWildcards,
+    /// 解釈できない解決ステップ
+    InvalidStep(String),
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "signature is empty"),
+            Self::InvalidToken(token) => write!(f, "invalid signature token '{}'", token),
+            Self::LengthMismatch => write!(f, "pattern and mask lengths differ"),
+            Self::OnlyWildcards => write!(f, "signature consists only of wildcards"),
+            Self::InvalidStep(step) => write!(f, "invalid resolve step '{}'", step),
+        }
+    }
+}
+
+impl ResolveStep {
+    /// "offset N" / "rel32" / "deref" を解析
+    fn parse(text: &str) -> Result<Self, SignatureError> {
+        let invalid = || SignatureError::InvalidStep(text.to_string());
+        let mut words = text.split_whitespace();
+        let step = match (words.next(), words.next()) {
+            (Some("offset"), Some(value)) => Self::Offset(parse_signed(value).ok_or_else(invalid)?),
+            (Some("rel32"), None) => Self::Rel32,
+            (Some("deref"), None) => Self::Deref,
+            _ => return Err(invalid()),
+        };
+        if words.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(step)
+    }
+}
+
+impl fmt::Display for ResolveStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Offset(value) => write!(f, "offset {}", value),
+            Self::Rel32 => write!(f, "rel32"),
+            Self::Deref => write!(f, "deref"),
+        }
+    }
+}
+
+/// 10進数または 0x 付き16進数の符号付き整数を解析
+fn parse_signed(text: &str) -> Option<isize> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => isize::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<isize>().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+impl Signature {
+    /// パターンとマスク（0xFF = 一致必須, 0x00 = ワイルドカード）から作成
+    pub fn from_bytes_and_mask(bytes: &[u8], mask: &[u8]) -> Result<Self, SignatureError> {
+        if bytes.len() != mask.len() {
+            return Err(SignatureError::LengthMismatch);
+        }
+
+        let pattern = bytes
+            .iter()
+            .zip(mask)
+            .map(|(&byte, &mask)| if mask == 0xFF { Some(byte) } else { None })
+            .collect();
+        Self::from_parts(pattern, Vec::new())
+    }
+
+    /// IDA形式の文字列から作成 (例: "48 8B C4 ?? 55", "48 8B 05 ?? ?? ?? ?? | offset 3, rel32, deref")
+    pub fn parse(text: &str) -> Result<Self, SignatureError> {
+        let (pattern_text, steps_text) = match text.split_once('|') {
+            Some((pattern, steps)) => (pattern, Some(steps)),
+            None => (text, None),
+        };
+
+        let pattern = pattern_text
+            .split_whitespace()
+            .map(|token| match token {
+                "?" | "??" => Ok(None),
+                _ if token.len() == 2 => u8::from_str_radix(token, 16)
+                    .map(Some)
+                    .map_err(|_| SignatureError::InvalidToken(token.to_string())),
+                _ => Err(SignatureError::InvalidToken(token.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let steps = match steps_text {
+            Some(steps) => steps
+                .split(',')
+                .map(|step| ResolveStep::parse(step.trim()))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
+        Self::from_parts(pattern, steps)
+    }
+
+    fn from_parts(pattern: Vec<Option<u8>>, steps: Vec<ResolveStep>) -> Result<Self, SignatureError> {
+        if pattern.is_empty() {
+            return Err(SignatureError::Empty);
+        }
+        if pattern.iter().all(Option::is_none) {
+            return Err(SignatureError::OnlyWildcards);
+        }
+        Ok(Self { pattern, steps })
+    }
+
+    /// パターンのバイト長
+    pub fn len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    /// 解決ステップ
+    pub fn steps(&self) -> &[ResolveStep] {
+        &self.steps
+    }
+
+    /// 指定位置がパターンに一致するかどうか
+    pub fn matches_at(&self, data: &[u8], offset: usize) -> bool {
+        match data.get(offset..offset.saturating_add(self.pattern.len())) {
+            Some(window) => self
+                .pattern
+                .iter()
+                .zip(window)
+                .all(|(expected, actual)| expected.is_none_or(|byte| byte == *actual)),
+            None => false,
+        }
+    }
+
+    /// データ内で最初に一致する位置を検索
+    pub fn find(&self, data: &[u8]) -> Option<usize> {
+        let last = data.len().checked_sub(self.pattern.len())?;
+        (0..=last).find(|&offset| self.matches_at(data, offset))
+    }
+
+    /// 一致位置から解決ステップを適用し、対象の RVA を求める
+    ///
+    /// `image` はモジュールの先頭からのメモリ、`image_base` はその先頭アドレス（deref 用）。
+    /// 途中でイメージ外を読む場合や、結果がイメージ外を指す場合は None。
+    pub fn resolve_at(&self, image: &[u8], image_base: usize, match_offset: usize) -> Option<usize> {
+        let mut cursor = match_offset;
+        for step in &self.steps {
+            cursor = match *step {
+                ResolveStep::Offset(delta) => cursor.checked_add_signed(delta)?,
+                ResolveStep::Rel32 => {
+                    let bytes = image.get(cursor..cursor.checked_add(4)?)?;
+                    let displacement = i32::from_le_bytes(bytes.try_into().ok()?);
+                    (cursor + 4).checked_add_signed(displacement as isize)?
+                }
+                ResolveStep::Deref => {
+                    let bytes = image.get(cursor..cursor.checked_add(8)?)?;
+                    let address = u64::from_le_bytes(bytes.try_into().ok()?) as usize;
+                    address.checked_sub(image_base)?
+                }
+            };
+        }
+        (cursor < image.len()).then_some(cursor)
+    }
+
+    /// イメージ全体を検索し、(一致位置, 解決後の RVA) を返す
+    pub fn scan(&self, image: &[u8], image_base: usize) -> Option<(usize, usize)> {
+        let match_offset = self.find(image)?;
+        let target = self.resolve_at(image, image_base, match_offset)?;
+        Some((match_offset, target))
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, byte) in self.pattern.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            match byte {
+                Some(byte) => write!(f, "{:02X}", byte)?,
+                None => write!(f, "??")?,
+            }
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            write!(f, "{}{}", if index == 0 { " | " } else { ", " }, step)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMAGE_BASE: usize = 0x1_4000_0000;
+
+    /// 0x100 バイトの空のイメージに命令を配置する
+    fn image_with(offset: usize, bytes: &[u8]) -> Vec<u8> {
+        let mut image = vec![0xCC; 0x100];
+        image[offset..offset + bytes.len()].copy_from_slice(bytes);
+        image
+    }
+
+    #[test]
+    fn resolves_call_target() {
+        // 0x10: call 0x80 (次の命令 0x15 からの相対 +0x6B); mov rbx, rax
+        let image = image_with(0x10, &[0xE8, 0x6B, 0x00, 0x00, 0x00, 0x48, 0x8B, 0xD8]);
+        let signature = Signature::parse("E8 ?? ?? ?? ?? 48 8B D8 | offset 1, rel32").unwrap();
+        assert_eq!(signature.scan(&image, IMAGE_BASE), Some((0x10, 0x80)));
+    }
+
+    #[test]
+    fn resolves_backward_call_target() {
+        // 0x40: call 0x20 (相対 -0x25)
+        let image = image_with(0x40, &[0xE8, 0xDB, 0xFF, 0xFF, 0xFF, 0x48, 0x8B, 0xD8]);
+        let signature = Signature::parse("E8 ?? ?? ?? ?? 48 8B D8 | offset 1, rel32").unwrap();
+        assert_eq!(signature.scan(&image, IMAGE_BASE), Some((0x40, 0x20)));
+    }
+
+    #[test]
+    fn resolves_rip_relative_pointer() {
+        // 0x20: mov rax, [rip+0x33] (0x27 + 0x33 = 0x5A), 0x5A には IMAGE_BASE + 0x90 が入っている
+        let mut image = image_with(0x20, &[0x48, 0x8B, 0x05, 0x33, 0x00, 0x00, 0x00]);
+        image[0x5A..0x62].copy_from_slice(&((IMAGE_BASE + 0x90) as u64).to_le_bytes());
+        let signature = Signature::parse("48 8B 05 ?? ?? ?? ?? | offset 3, rel32, deref").unwrap();
+        assert_eq!(signature.scan(&image, IMAGE_BASE), Some((0x20, 0x90)));
+    }
+
+    #[test]
+    fn applies_negative_offset() {
+        let image = image_with(0x30, &[0x48, 0x89, 0x5C, 0x24, 0x08]);
+        let signature = Signature::parse("48 89 5C 24 08 | offset -0x10").unwrap();
+        assert_eq!(signature.scan(&image, IMAGE_BASE), Some((0x30, 0x20)));
+
+        let signature = Signature::parse("48 89 5C 24 08 | offset -49").unwrap();
+        assert_eq!(signature.scan(&image, IMAGE_BASE), None);
+    }
+
+    #[test]
+    fn rejects_targets_outside_the_image() {
+        // 呼び出し先がイメージの後ろ
+        let image = image_with(0x10, &[0xE8, 0x00, 0x10, 0x00, 0x00, 0x48, 0x8B, 0xD8]);
+        let signature = Signature::parse("E8 ?? ?? ?? ?? 48 8B D8 | offset 1, rel32").unwrap();
+        assert_eq!(signature.resolve_at(&image, IMAGE_BASE, 0x10), None);
+
+        // 呼び出し先がイメージの前
+        let image = image_with(0x10, &[0xE8, 0x00, 0xFF, 0xFF, 0xFF, 0x48, 0x8B, 0xD8]);
+        assert_eq!(signature.resolve_at(&image, IMAGE_BASE, 0x10), None);
+
+        // 読み込んだポインタが別のモジュールを指す
+        let mut image = image_with(0x20, &[0x48, 0x8B, 0x05, 0x33, 0x00, 0x00, 0x00]);
+        image[0x5A..0x62].copy_from_slice(&0x7FF0_0000_0000u64.to_le_bytes());
+        let signature = Signature::parse("48 8B 05 ?? ?? ?? ?? | offset 3, rel32, deref").unwrap();
+        assert_eq!(signature.resolve_at(&image, IMAGE_BASE, 0x20), None);
+
+        // 相対値の途中でイメージが終わる
+        let signature = Signature::parse("CC | offset 2, rel32").unwrap();
+        assert_eq!(signature.resolve_at(&image, IMAGE_BASE, 0xFC), None);
+    }
+
+    #[test]
+    fn parses_resolve_steps() {
+        let signature = Signature::parse("48 8B 05 ?? ?? ?? ?? | offset 3, rel32, deref").unwrap();
+        assert_eq!(signature.steps(), &[ResolveStep::Offset(3), ResolveStep::Rel32, ResolveStep::Deref]);
+        assert_eq!(ResolveStep::parse("offset -0x10"), Ok(ResolveStep::Offset(-0x10)));
+        assert_eq!(ResolveStep::parse("offset +8"), Ok(ResolveStep::Offset(8)));
+        assert_eq!(signature.to_string(), "48 8B 05 ?? ?? ?? ?? | offset 3, rel32, deref");
+    }
+
+    #[test]
+    fn rejects_invalid_resolve_steps() {
+        for step in ["offset", "offset x", "offset 1 2", "rel32 4", "deref deref", "jump", ""] {
+            assert_eq!(ResolveStep::parse(step), Err(SignatureError::InvalidStep(step.to_string())), "{step}");
+        }
+        assert_eq!(
+            Signature::parse("E8 ?? ?? ?? ?? | offset 1, rel"),
+            Err(SignatureError::InvalidStep("rel".to_string()))
+        );
+        assert_eq!(Signature::parse("E8 | "), Err(SignatureError::InvalidStep(String::new())));
+    }
+}
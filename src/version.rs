@@ -0,0 +1,405 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// VS_FIXEDFILEINFO の dwSignature
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF_04BD;
+/// VS_FIXEDFILEINFO のサイズ
+const FIXED_FILE_INFO_SIZE: usize = 52;
+/// VS_VERSIONINFO ノードのヘッダサイズ (wLength, wValueLength, wType)
+const NODE_HEADER_SIZE: usize = 6;
+
+// 起動時に検出したゲームのバージョン
+static GAME_VERSION: OnceLock<Option<GameVersion>> = OnceLock::new();
+
+/// ゲームのバージョン (例: 1.21.124.2)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GameVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub build: u16,
+    pub revision: u16,
+}
+
+impl GameVersion {
+    pub const fn new(major: u16, minor: u16, build: u16, revision: u16) -> Self {
+        Self { major, minor, build, revision }
+    }
+
+    /// リビジョン（ホットフィックス番号）を除いて一致するかどうか
+    pub fn same_release(&self, other: &GameVersion) -> bool {
+        (self.major, self.minor, self.build) == (other.major, other.minor, other.build)
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}.{}", self.major, self.minor, self.build, self.revision)
+    }
+}
+
+impl FromStr for GameVersion {
+    type Err = VersionInfoError;
+
+    /// "1.21.124.2" や "1, 21, 124, 2" 形式の文字列を解析（省略された要素は 0）
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || VersionInfoError::InvalidVersionString(text.to_string());
+        let parts = text
+            .trim()
+            .split(['.', ','])
+            .map(|part| part.trim().parse::<u16>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if parts.is_empty() || parts.len() > 4 {
+            return Err(invalid());
+        }
+
+        let part = |index: usize| parts.get(index).copied().unwrap_or(0);
+        Ok(Self::new(part(0), part(1), part(2), part(3)))
+    }
+}
+
+/// バージョンリソースの解析エラー
+#[derive(Debug, PartialEq, Eq)]
+pub enum VersionInfoError {
+    /// バージョンリソースが存在しない
+    NotFound,
+    /// データが途中で終わっている、または長さが不正
+    Truncated,
+    /// ルートノードのキーが VS_VERSION_INFO ではない
+    UnexpectedKey(String),
+    /// VS_FIXEDFILEINFO のシグネチャが不正
+    BadSignature(u32),
+    /// 固定情報にも文字列情報にも製品バージョンがない
+    MissingProductVersion,
+    /// ProductVersion 文字列を解析できない
+    InvalidVersionString(String),
+}
+
+impl fmt::Display for VersionInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "version resource not found"),
+            Self::Truncated => write!(f, "version resource is truncated"),
+            Self::UnexpectedKey(key) => write!(f, "unexpected root key '{}'", key),
+            Self::BadSignature(signature) => {
+                write!(f, "bad VS_FIXEDFILEINFO signature 0x{:08X}", signature)
+            }
+            Self::MissingProductVersion => write!(f, "no product version in version resource"),
+            Self::InvalidVersionString(text) => write!(f, "invalid version string '{}'", text),
+        }
+    }
+}
+
+/// VS_VERSIONINFO 内の1ノード (VS_VERSIONINFO / StringFileInfo / StringTable / String)
+struct VersionNode<'a> {
+    key: String,
+    /// wType == 1 の場合は UTF-16 文字列
+    is_text: bool,
+    value: &'a [u8],
+    children: &'a [u8],
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, VersionInfoError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or(VersionInfoError::Truncated)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, VersionInfoError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(VersionInfoError::Truncated)
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// NUL終端の UTF-16 文字列を読み取り、(文字列, 終端の次の位置) を返す
+fn read_utf16z(data: &[u8], offset: usize) -> Result<(String, usize), VersionInfoError> {
+    let mut units = Vec::new();
+    let mut position = offset;
+    loop {
+        let unit = read_u16(data, position)?;
+        position += 2;
+        if unit == 0 {
+            break;
+        }
+        units.push(unit);
+    }
+    Ok((String::from_utf16_lossy(&units), position))
+}
+
+/// ノードを1つ解析し、(ノード, 次のノードまでのバイト数) を返す
+fn parse_node(data: &[u8]) -> Result<(VersionNode<'_>, usize), VersionInfoError> {
+    let length = read_u16(data, 0)? as usize;
+    let value_length = read_u16(data, 2)? as usize;
+    let is_text = read_u16(data, 4)? == 1;
+
+    if length < NODE_HEADER_SIZE || length > data.len() {
+        return Err(VersionInfoError::Truncated);
+    }
+    let node = &data[..length];
+
+    let (key, key_end) = read_utf16z(node, NODE_HEADER_SIZE)?;
+    let value_start = align4(key_end);
+    // 文字列値の wValueLength は文字数（WCHAR単位）
+    let value_bytes = if is_text { value_length * 2 } else { value_length };
+    let mut value_end = value_start + value_bytes;
+    if is_text {
+        // リソースコンパイラによってはバイト数が入っているため、ノード内に収める
+        value_end = value_end.min(node.len());
+    }
+    if value_start > value_end || value_end > node.len() {
+        return Err(VersionInfoError::Truncated);
+    }
+
+    let children_start = align4(value_end).min(node.len());
+    Ok((
+        VersionNode {
+            key,
+            is_text,
+            value: &node[value_start..value_end],
+            children: &node[children_start..],
+        },
+        align4(length),
+    ))
+}
+
+/// 子ノードを順に解析する
+fn parse_children(data: &[u8]) -> Result<Vec<VersionNode<'_>>, VersionInfoError> {
+    let mut nodes = Vec::new();
+    let mut offset = 0;
+    while offset + NODE_HEADER_SIZE <= data.len() {
+        let (node, advance) = parse_node(&data[offset..])?;
+        nodes.push(node);
+        offset += advance;
+    }
+    Ok(nodes)
+}
+
+/// VS_FIXEDFILEINFO から製品バージョンを読み取る
+fn fixed_product_version(value: &[u8]) -> Result<Option<GameVersion>, VersionInfoError> {
+    if value.len() < FIXED_FILE_INFO_SIZE {
+        return Ok(None);
+    }
+
+    let signature = read_u32(value, 0)?;
+    if signature != FIXED_FILE_INFO_SIGNATURE {
+        return Err(VersionInfoError::BadSignature(signature));
+    }
+
+    let product_ms = read_u32(value, 16)?;
+    let product_ls = read_u32(value, 20)?;
+    if product_ms == 0 && product_ls == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(GameVersion::new(
+        (product_ms >> 16) as u16,
+        (product_ms & 0xFFFF) as u16,
+        (product_ls >> 16) as u16,
+        (product_ls & 0xFFFF) as u16,
+    )))
+}
+
+/// StringFileInfo 内の ProductVersion 文字列を探す
+fn string_product_version(children: &[u8]) -> Result<Option<String>, VersionInfoError> {
+    for file_info in parse_children(children)? {
+        if file_info.key != "StringFileInfo" {
+            continue;
+        }
+        for table in parse_children(file_info.children)? {
+            for entry in parse_children(table.children)? {
+                if entry.key == "ProductVersion" && entry.is_text {
+                    let units: Vec<u16> = entry
+                        .value
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                        .take_while(|&unit| unit != 0)
+                        .collect();
+                    let text = String::from_utf16_lossy(&units);
+                    return Ok(Some(text));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// VS_VERSIONINFO リソースから製品バージョンを取得
+///
+/// VS_FIXEDFILEINFO を優先し、無い場合は StringFileInfo の ProductVersion を使用する。
+pub fn parse_version_info(data: &[u8]) -> Result<GameVersion, VersionInfoError> {
+    let (root, _) = parse_node(data)?;
+    if root.key != "VS_VERSION_INFO" {
+        return Err(VersionInfoError::UnexpectedKey(root.key));
+    }
+
+    if let Some(version) = fixed_product_version(root.value)? {
+        return Ok(version);
+    }
+
+    match string_product_version(root.children)? {
+        Some(text) => text.parse(),
+        None => Err(VersionInfoError::MissingProductVersion),
+    }
+}
+
+/// 整数のリソースIDをリソース名として渡す (MAKEINTRESOURCE)
+#[cfg(target_os = "windows")]
+fn make_int_resource(id: u16) -> windows::core::PCWSTR {
+    windows::core::PCWSTR(id as usize as *const u16)
+}
+
+/// 実行ファイルのバージョンリソースを読み取る
+#[cfg(target_os = "windows")]
+fn read_executable_version() -> Result<GameVersion, VersionInfoError> {
+    use windows::Win32::System::LibraryLoader::{
+        FindResourceW, GetModuleHandleW, LoadResource, LockResource, SizeofResource,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::RT_VERSION;
+
+    unsafe {
+        let module = GetModuleHandleW(None).map_err(|_| VersionInfoError::NotFound)?;
+        // VS_VERSION_INFO のリソースIDは 1
+        let resource = FindResourceW(module, make_int_resource(1), RT_VERSION);
+        if resource.0 == 0 {
+            return Err(VersionInfoError::NotFound);
+        }
+
+        let size = SizeofResource(module, resource) as usize;
+        let handle = LoadResource(module, resource).map_err(|_| VersionInfoError::NotFound)?;
+        let data = LockResource(handle) as *const u8;
+        if data.is_null() || size == 0 {
+            return Err(VersionInfoError::NotFound);
+        }
+
+        parse_version_info(std::slice::from_raw_parts(data, size))
+    }
+}
+
+/// ゲームのバージョンを検出してログに記録する（2回目以降は検出済みの値を返す）
+#[cfg(target_os = "windows")]
+pub fn detect() -> Option<GameVersion> {
+    *GAME_VERSION.get_or_init(|| match read_executable_version() {
+        Ok(version) => {
+            crate::logger::info(&format!("game version: {}", version));
+            Some(version)
+        }
+        Err(error) => {
+            crate::logger::warn(&format!("failed to detect game version: {}", error));
+            None
+        }
+    })
+}
+
+/// 検出済みのゲームバージョンを取得（未検出または検出失敗時は None）
+pub fn game_version() -> Option<GameVersion> {
+    GAME_VERSION.get().copied().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16z(text: &str) -> Vec<u8> {
+        text.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn pad4(bytes: &mut Vec<u8>) {
+        bytes.resize(align4(bytes.len()), 0);
+    }
+
+    /// VS_VERSIONINFO 形式のノードを組み立てる
+    fn node(key: &str, value: &[u8], is_text: bool, children: &[Vec<u8>]) -> Vec<u8> {
+        let value_length = if is_text { value.len() / 2 } else { value.len() };
+        let mut bytes = vec![0, 0];
+        bytes.extend((value_length as u16).to_le_bytes());
+        bytes.extend((is_text as u16).to_le_bytes());
+        bytes.extend(utf16z(key));
+        pad4(&mut bytes);
+        bytes.extend(value);
+        for child in children {
+            pad4(&mut bytes);
+            bytes.extend(child);
+        }
+        let length = bytes.len() as u16;
+        bytes[..2].copy_from_slice(&length.to_le_bytes());
+        bytes
+    }
+
+    fn fixed_info(signature: u32, product_ms: u32, product_ls: u32) -> Vec<u8> {
+        let mut value = vec![0; FIXED_FILE_INFO_SIZE];
+        value[0..4].copy_from_slice(&signature.to_le_bytes());
+        value[16..20].copy_from_slice(&product_ms.to_le_bytes());
+        value[20..24].copy_from_slice(&product_ls.to_le_bytes());
+        value
+    }
+
+    fn string_file_info(product_version: &str) -> Vec<u8> {
+        let entry = node("ProductVersion", &utf16z(product_version), true, &[]);
+        let table = node("040904B0", &[], false, &[entry]);
+        node("StringFileInfo", &[], false, &[table])
+    }
+
+    #[test]
+    fn reads_product_version_from_fixed_info() {
+        let data = node(
+            "VS_VERSION_INFO",
+            &fixed_info(FIXED_FILE_INFO_SIGNATURE, (1 << 16) | 21, (124 << 16) | 2),
+            false,
+            &[string_file_info("9.9.9.9")],
+        );
+        assert_eq!(parse_version_info(&data), Ok(GameVersion::new(1, 21, 124, 2)));
+    }
+
+    #[test]
+    fn falls_back_to_string_product_version() {
+        let data = node(
+            "VS_VERSION_INFO",
+            &fixed_info(FIXED_FILE_INFO_SIGNATURE, 0, 0),
+            false,
+            &[string_file_info("1, 21, 130, 3")],
+        );
+        assert_eq!(parse_version_info(&data), Ok(GameVersion::new(1, 21, 130, 3)));
+
+        let without_fixed_info = node("VS_VERSION_INFO", &[], false, &[string_file_info("1.21.130")]);
+        assert_eq!(parse_version_info(&without_fixed_info), Ok(GameVersion::new(1, 21, 130, 0)));
+    }
+
+    #[test]
+    fn reports_missing_product_version() {
+        let data = node("VS_VERSION_INFO", &fixed_info(FIXED_FILE_INFO_SIGNATURE, 0, 0), false, &[]);
+        assert_eq!(parse_version_info(&data), Err(VersionInfoError::MissingProductVersion));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let data = node(
+            "VS_VERSION_INFO",
+            &fixed_info(FIXED_FILE_INFO_SIGNATURE, (1 << 16) | 21, (124 << 16) | 2),
+            false,
+            &[],
+        );
+        assert_eq!(parse_version_info(&data[..data.len() - 8]), Err(VersionInfoError::Truncated));
+        assert_eq!(parse_version_info(&data[..4]), Err(VersionInfoError::Truncated));
+        assert_eq!(parse_version_info(&[]), Err(VersionInfoError::Truncated));
+    }
+
+    #[test]
+    fn rejects_bad_signature() {
+        let data = node("VS_VERSION_INFO", &fixed_info(0x1234_5678, 1 << 16, 0), false, &[]);
+        assert_eq!(parse_version_info(&data), Err(VersionInfoError::BadSignature(0x1234_5678)));
+    }
+
+    #[test]
+    fn rejects_wrong_root_key() {
+        let data = node("VS_VERSION_INFX", &fixed_info(FIXED_FILE_INFO_SIGNATURE, 1 << 16, 0), false, &[]);
+        assert_eq!(
+            parse_version_info(&data),
+            Err(VersionInfoError::UnexpectedKey("VS_VERSION_INFX".to_string()))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+use crate::input::{InputEvent, TimedEvent, WheelAxis};
+
+/// 1ノッチあたりのホイールデルタ
+pub const WHEEL_DELTA: f32 = 120.0;
+
+/// ホイールイベントの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WheelAction {
+    /// ゲームや他のアプリにそのまま渡す
+    Forward,
+    /// ズームの調整にだけ使い、ゲームには渡さない（ホットバーが切り替わらないように）
+    Consume,
+}
+
+/// 横ホイールで調整する項目
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HorizontalWheelAction {
+    /// 何もしない（ゲームにそのまま渡す）
+    None,
+    /// アニメーション速度を調整
+    AnimationSpeed,
+}
+
+impl HorizontalWheelAction {
+    /// 設定値を項目に変換（不明な値は none）
+    pub fn parse(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "animation_speed" => Self::AnimationSpeed,
+            _ => Self::None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::AnimationSpeed => "animation_speed",
+        }
+    }
+}
+
+/// ホイールに関する設定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WheelSettings {
+    /// ズーム中にスクロールで倍率を調整する
+    pub scroll_adjustment: bool,
+    /// ズームの調整に使ったホイールイベントをゲームに渡さない
+    pub consume_while_zooming: bool,
+    /// ズーム中の横ホイールで調整する項目
+    pub horizontal: HorizontalWheelAction,
+}
+
+impl Default for WheelSettings {
+    fn default() -> Self {
+        Self {
+            scroll_adjustment: false,
+            consume_while_zooming: false,
+            horizontal: HorizontalWheelAction::None,
+        }
+    }
+}
+
+/// ホイールイベントをゲームに渡すか決める
+///
+/// ゲームのウィンドウにフォーカスがあり、ズームキーでその方向のホイールを使っている間だけ消費する。
+pub fn wheel_action(settings: WheelSettings, axis: WheelAxis, focused: bool, zoom_key_held: bool) -> WheelAction {
+    let bound = match axis {
+        WheelAxis::Vertical => settings.scroll_adjustment,
+        WheelAxis::Horizontal => settings.horizontal != HorizontalWheelAction::None,
+    };
+    if focused && zoom_key_held && bound && settings.consume_while_zooming {
+        WheelAction::Consume
+    } else {
+        WheelAction::Forward
+    }
+}
+
+/// ホイールデルタの端数を蓄積し、不感帯を超えた分をノッチ単位で返す
+///
+/// 高精度のホイールやタッチパッドが送る小さなデルタも通常のホイールと同じ量だけ調整し、
+/// 不感帯より小さい揺れは無視する。しばらく止まるか向きが変わると蓄積を捨てる。
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WheelAccumulator {
+    pending: f32,
+    last_time: Option<Duration>,
+}
+
+impl WheelAccumulator {
+    /// この時間イベントがなければ蓄積を捨てる
+    pub const IDLE_RESET: Duration = Duration::from_millis(300);
+
+    pub const fn new() -> Self {
+        Self { pending: 0.0, last_time: None }
+    }
+
+    /// デルタを1つ加え、不感帯（ノッチ単位）を超えたら蓄積したノッチ数を返す
+    pub fn feed(&mut self, delta: i32, time: Duration, dead_zone: f32) -> f32 {
+        if self.last_time.is_some_and(|last| time.saturating_sub(last) > Self::IDLE_RESET) {
+            self.pending = 0.0;
+        }
+        self.last_time = Some(time);
+
+        let notches = delta as f32 / WHEEL_DELTA;
+        // 向きが変わったら逆向きの端数は捨てる
+        if self.pending * notches < 0.0 {
+            self.pending = 0.0;
+        }
+        self.pending += notches;
+
+        if self.pending.abs() >= dead_zone && self.pending != 0.0 {
+            std::mem::take(&mut self.pending)
+        } else {
+            0.0
+        }
+    }
+
+    /// フレームのイベントのうち指定した方向のホイールをすべて加え、合計のノッチ数を返す
+    pub fn feed_events(&mut self, events: &[TimedEvent], axis: WheelAxis, dead_zone: f32) -> f32 {
+        events
+            .iter()
+            .filter_map(|timed| match timed.event {
+                InputEvent::Wheel { axis: event_axis, delta } if event_axis == axis => {
+                    Some(self.feed(delta, timed.time, dead_zone))
+                }
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// 蓄積を捨てる（ズームをやめたときなど）
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(scroll_adjustment: bool, horizontal: HorizontalWheelAction, consume: bool) -> WheelSettings {
+        WheelSettings { scroll_adjustment, consume_while_zooming: consume, horizontal }
+    }
+
+    #[test]
+    fn wheel_action_consumes_only_when_every_condition_holds() {
+        let horizontal_actions = [HorizontalWheelAction::None, HorizontalWheelAction::AnimationSpeed];
+        for scroll_adjustment in [false, true] {
+            for horizontal in horizontal_actions {
+                for consume in [false, true] {
+                    for focused in [false, true] {
+                        for held in [false, true] {
+                            let settings = settings(scroll_adjustment, horizontal, consume);
+                            let gate = focused && held && consume;
+
+                            let vertical = wheel_action(settings, WheelAxis::Vertical, focused, held);
+                            let expected = if gate && scroll_adjustment { WheelAction::Consume } else { WheelAction::Forward };
+                            assert_eq!(vertical, expected, "vertical {:?} focused={} held={}", settings, focused, held);
+
+                            let horizontal_bound = horizontal != HorizontalWheelAction::None;
+                            let sideways = wheel_action(settings, WheelAxis::Horizontal, focused, held);
+                            let expected = if gate && horizontal_bound { WheelAction::Consume } else { WheelAction::Forward };
+                            assert_eq!(sideways, expected, "horizontal {:?} focused={} held={}", settings, focused, held);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn axis_binding_is_independent() {
+        let vertical_only = settings(true, HorizontalWheelAction::None, true);
+        assert_eq!(wheel_action(vertical_only, WheelAxis::Vertical, true, true), WheelAction::Consume);
+        assert_eq!(wheel_action(vertical_only, WheelAxis::Horizontal, true, true), WheelAction::Forward);
+
+        let horizontal_only = settings(false, HorizontalWheelAction::AnimationSpeed, true);
+        assert_eq!(wheel_action(horizontal_only, WheelAxis::Vertical, true, true), WheelAction::Forward);
+        assert_eq!(wheel_action(horizontal_only, WheelAxis::Horizontal, true, true), WheelAction::Consume);
+    }
+
+    #[test]
+    fn horizontal_action_parse_round_trips() {
+        for action in [HorizontalWheelAction::None, HorizontalWheelAction::AnimationSpeed] {
+            assert_eq!(HorizontalWheelAction::parse(action.as_str()), action);
+        }
+        assert_eq!(HorizontalWheelAction::parse("ANIMATION_SPEED"), HorizontalWheelAction::AnimationSpeed);
+        assert_eq!(HorizontalWheelAction::parse("bogus"), HorizontalWheelAction::None);
+    }
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn full_notch_passes_through() {
+        let mut accumulator = WheelAccumulator::new();
+        assert_eq!(accumulator.feed(120, ms(0), 1.0), 1.0);
+        assert_eq!(accumulator.feed(-240, ms(10), 1.0), -2.0);
+    }
+
+    #[test]
+    fn fractional_deltas_accumulate_to_a_notch() {
+        let mut accumulator = WheelAccumulator::new();
+        assert_eq!(accumulator.feed(40, ms(0), 1.0), 0.0);
+        assert_eq!(accumulator.feed(40, ms(10), 1.0), 0.0);
+        let notches = accumulator.feed(40, ms(20), 1.0);
+        assert!((notches - 1.0).abs() < 1e-6);
+        // 返した分は蓄積から消える
+        assert_eq!(accumulator.feed(40, ms(30), 1.0), 0.0);
+    }
+
+    #[test]
+    fn dead_zone_ignores_small_jitter() {
+        let mut accumulator = WheelAccumulator::new();
+        assert_eq!(accumulator.feed(30, ms(0), 0.5), 0.0);
+        assert_eq!(accumulator.feed(29, ms(10), 0.5), 0.0);
+        assert!((accumulator.feed(1, ms(20), 0.5) - 0.5).abs() < 1e-6);
+        // 不感帯が 0 なら小さいデルタもすぐに返す
+        let mut accumulator = WheelAccumulator::new();
+        assert!((accumulator.feed(12, ms(0), 0.0) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn direction_reversal_discards_the_remainder() {
+        let mut accumulator = WheelAccumulator::new();
+        assert_eq!(accumulator.feed(100, ms(0), 1.0), 0.0);
+        assert_eq!(accumulator.feed(-60, ms(10), 1.0), 0.0);
+        // 上向きの 100 は捨てられ、下向きの 60 + 60 で1ノッチ
+        assert!((accumulator.feed(-60, ms(20), 1.0) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn idle_gap_resets_the_remainder() {
+        let mut accumulator = WheelAccumulator::new();
+        assert_eq!(accumulator.feed(100, ms(0), 1.0), 0.0);
+        let after_idle = WheelAccumulator::IDLE_RESET + ms(1);
+        assert_eq!(accumulator.feed(100, after_idle, 1.0), 0.0);
+        // ちょうど IDLE_RESET の間隔なら蓄積は残る
+        let mut accumulator = WheelAccumulator::new();
+        assert_eq!(accumulator.feed(100, ms(0), 1.0), 0.0);
+        assert!(accumulator.feed(20, WheelAccumulator::IDLE_RESET, 1.0) > 0.0);
+    }
+
+    #[test]
+    fn feed_events_uses_only_the_requested_axis() {
+        let events = [
+            TimedEvent::new(ms(0), InputEvent::Wheel { axis: WheelAxis::Vertical, delta: 60 }),
+            TimedEvent::new(ms(1), InputEvent::Wheel { axis: WheelAxis::Horizontal, delta: 120 }),
+            TimedEvent::new(ms(2), InputEvent::Key { key: 0x41, down: true }),
+            TimedEvent::new(ms(3), InputEvent::Wheel { axis: WheelAxis::Vertical, delta: 60 }),
+        ];
+        let mut accumulator = WheelAccumulator::new();
+        assert!((accumulator.feed_events(&events, WheelAxis::Vertical, 1.0) - 1.0).abs() < 1e-6);
+        let mut accumulator = WheelAccumulator::new();
+        assert!((accumulator.feed_events(&events, WheelAxis::Horizontal, 1.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reset_discards_the_remainder() {
+        let mut accumulator = WheelAccumulator::new();
+        accumulator.feed(100, ms(0), 1.0);
+        accumulator.reset();
+        assert_eq!(accumulator.feed(40, ms(10), 1.0), 0.0);
+    }
+}
@@ -1,7 +1,14 @@
 use std::ffi::c_void;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
 use windows::Win32::Foundation::{BOOL, HMODULE};
 use windows::Win32::System::SystemServices::{DLL_PROCESS_ATTACH, DLL_PROCESS_DETACH};
-use minhook_sys::*;
+
+use crate::hook_backend::HOOKS;
+use crate::lifecycle::{LifecycleState, StartupError, LIFECYCLE};
+
+// 終了時に初期化の完了を待つための起動用スレッド
+static STARTUP_THREAD: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
 
 #[no_mangle]
 #[allow(non_snake_case, unused_variables)]
@@ -12,21 +19,88 @@ pub extern "system" fn DllMain(
 ) -> BOOL {
     match call_reason {
         DLL_PROCESS_ATTACH => {
-            std::thread::spawn(|| {
-                unsafe { 
-                    initialize(); 
-                    crate::zoom::initialize();
-                }
-            });
+            let handle = std::thread::spawn(|| unsafe { start() });
+            if let Ok(mut thread) = STARTUP_THREAD.lock() {
+                *thread = Some(handle);
+            }
+        }
+        DLL_PROCESS_DETACH => {
+            if reserved.is_null() {
+                // FreeLibrary によるアンロード：ローダーロック中は待たず、各スレッドに終了を依頼するだけにする
+                // （フックの解除は事前にエクスポートの shutdown で行う必要がある）
+                crate::zoom::cancel_startup();
+                crate::input_hooks::request_stop();
+            } else {
+                // プロセス終了：他のスレッドは停止済みのため設定の保存だけ行う
+                crate::config_manager::flush_pending_save_on_exit();
+            }
         }
-        DLL_PROCESS_DETACH => {}
         _ => {}
     }
     BOOL::from(true)
 }
 
-unsafe fn initialize() {
-    if MH_Initialize() != MH_OK {
-        return;
+/// Modを終了する（アンロード前に外部から呼び出すためのエクスポート）
+///
+/// FreeLibrary の前に必ず呼び出すこと。DllMain ではローダーロックのためフックを外せない。
+/// 起動用スレッドと入力フックのスレッドの終了まで待ち、フックを外せたら TRUE を返す
+/// （FALSE の場合は renderLevel の実行中にフックが残っているためアンロードしてはいけない）。
+#[no_mangle]
+pub extern "system" fn shutdown() -> BOOL {
+    // スキャンの再試行を打ち切り、初期化が途中でフックを作成し終えるのを待ってから外す
+    crate::zoom::cancel_startup();
+    let startup = STARTUP_THREAD.lock().ok().and_then(|mut thread| thread.take());
+    if let Some(handle) = startup {
+        if handle.join().is_err() {
+            crate::logger::warn("startup thread panicked");
+        }
+    }
+    let removed = unsafe { crate::zoom::shutdown() };
+    crate::input_hooks::join();
+    BOOL::from(removed)
+}
+
+/// Modの状態を返す（0: uninitialized, 1: scanning, 2: hooked, 3: degraded, 4: failed, 5: shut down）
+#[no_mangle]
+pub extern "system" fn zoom_status() -> u32 {
+    LIFECYCLE.state().code() as u32
+}
+
+/// 状態と理由、入力フックの状態を UTF-8 で `buffer` に書き込み、必要なバイト数を返す
+///
+/// # Safety
+/// `buffer` が null でない場合、`capacity` バイト書き込み可能であること。
+#[no_mangle]
+pub unsafe extern "system" fn zoom_status_text(buffer: *mut u8, capacity: usize) -> usize {
+    let text = format!("{}; {}", LIFECYCLE.status(), crate::input_hooks::status());
+    if !buffer.is_null() {
+        let length = text.len().min(capacity);
+        std::ptr::copy_nonoverlapping(text.as_ptr(), buffer, length);
     }
+    text.len()
+}
+
+/// 初期化の各段階を順に実行し、失敗したら以降の段階を実行しない
+unsafe fn start() {
+    crate::logger::init();
+    
+    let result = initialize()
+        .and_then(|()| crate::zoom::initialize())
+        .and_then(|()| {
+            LIFECYCLE
+                .transition(LifecycleState::Hooked, None)
+                .map_err(|_| StartupError::Cancelled)
+        });
+    if let Err(error) = result {
+        if LIFECYCLE.fail(&error).is_err() {
+            crate::logger::warn(&format!("startup stopped: {}", error));
+        }
+    }
+}
+
+unsafe fn initialize() -> Result<(), StartupError> {
+    LIFECYCLE
+        .transition(LifecycleState::Scanning, None)
+        .map_err(|_| StartupError::Cancelled)?;
+    HOOKS.attach().map_err(StartupError::Backend)
 }
@@ -0,0 +1,68 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::UI::Input::XboxController::{XInputGetState, XINPUT_STATE, XUSER_MAX_COUNT};
+
+use crate::gamepad::{GamepadSource, GamepadState};
+
+/// 未接続のときに再びコントローラーを探す間隔（未接続のスロットの読み取りは遅いため）
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+struct XInputState {
+    /// 最後に読み取れたスロット
+    user_index: Option<u32>,
+    last_search: Option<Instant>,
+}
+
+/// XInput で最初に見つかったコントローラーを読む
+pub struct XInputGamepad {
+    state: Mutex<XInputState>,
+}
+
+impl XInputGamepad {
+    pub const fn new() -> Self {
+        Self { state: Mutex::new(XInputState { user_index: None, last_search: None }) }
+    }
+}
+
+impl Default for XInputGamepad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read_slot(user_index: u32) -> Option<GamepadState> {
+    let mut state = XINPUT_STATE::default();
+    if unsafe { XInputGetState(user_index, &mut state) } != ERROR_SUCCESS.0 {
+        return None;
+    }
+    Some(GamepadState {
+        buttons: state.Gamepad.wButtons.0,
+        left_trigger: state.Gamepad.bLeftTrigger,
+        right_trigger: state.Gamepad.bRightTrigger,
+    })
+}
+
+impl GamepadSource for XInputGamepad {
+    fn read(&self) -> Option<GamepadState> {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(user_index) = state.user_index {
+            if let Some(gamepad) = read_slot(user_index) {
+                return Some(gamepad);
+            }
+            state.user_index = None;
+        }
+
+        let now = Instant::now();
+        if state.last_search.is_some_and(|last| now.duration_since(last) < RECONNECT_INTERVAL) {
+            return None;
+        }
+        state.last_search = Some(now);
+        (0..XUSER_MAX_COUNT).find_map(|user_index| {
+            let gamepad = read_slot(user_index)?;
+            state.user_index = Some(user_index);
+            Some(gamepad)
+        })
+    }
+}
@@ -1,148 +1,445 @@
 use std::ffi::c_void;
-use std::sync::atomic::{AtomicUsize, AtomicI32, AtomicBool, Ordering};
+use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
 use std::thread;
-use minhook_sys::*;
-use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
-use windows::Win32::UI::WindowsAndMessaging::{
-    SetWindowsHookExW, CallNextHookEx, GetMessageW,
-    WH_MOUSE_LL, MSLLHOOKSTRUCT, HHOOK, MSG,
-};
-use windows::Win32::Foundation::{WPARAM, LPARAM, LRESULT};
+use std::time::{Duration, Instant};
 
-use crate::config_manager::{ZoomConfig, get_config, init_config};
+use crate::config_manager::{
+    ZoomConfig, ZoomMode, flush_due_save, flush_pending_save, get_config, init_config, save_deferred,
+};
+use crate::focus::FocusProvider;
+use crate::foreground_focus::{self, ForegroundFocus};
+use crate::fov_guard::{FovGuard, FovProblem, GuardVerdict};
+use crate::fov_math;
+use crate::fov_patch::{FovFields, FovPair, FovPatch, RestoreOutcome};
+use crate::gamepad::{GamepadFrame, GamepadInput, GamepadSource, GamepadTracker};
+use crate::input::{self, FrameInput, HookRequirements, KeyTracker, WheelAxis};
+use crate::input_hooks::{self, INPUT_EVENTS};
+use crate::input_context::{ContextSample, ContextTracker, InputContext, KeyGate, SuppressionSettings};
+use crate::layout::PlayerFov;
+use crate::lifecycle::{LifecycleState, StartupError, LIFECYCLE};
+use crate::hook_backend::HOOKS;
+use crate::hook_cache::HookCache;
+use crate::logger;
+use crate::offsets::{self, GameOffsets};
+use crate::hook_target::ResolvedTarget;
+use crate::prologue;
+use crate::retry::{self, CancelToken, RetryPolicy};
+use crate::version;
+use crate::wheel::{HorizontalWheelAction, WheelAccumulator, WheelSettings};
+use crate::xinput_gamepad::XInputGamepad;
+use crate::zoom_controller::{AnalogSettings, BindingSelector, StopSettings, ZoomController, ZoomInput, ZoomStops};
 
 static ORIGINAL_RENDER_LEVEL: AtomicUsize = AtomicUsize::new(0);
-static MOUSE_HOOK: AtomicUsize = AtomicUsize::new(0);
-static SCROLL_DELTA: AtomicI32 = AtomicI32::new(0);
-static ZOOM_KEY_PRESSED: AtomicBool = AtomicBool::new(false);
+static RENDER_LEVEL_TARGET: AtomicUsize = AtomicUsize::new(0);
+static OFFSETS: OnceLock<GameOffsets> = OnceLock::new();
+static FOV_GUARD: Mutex<FovGuard> = Mutex::new(FovGuard::new());
+static LAST_PLAYER: AtomicUsize = AtomicUsize::new(0);
+static FOCUS: ForegroundFocus = ForegroundFocus::new();
+static KEY_GATE: Mutex<KeyGate> = Mutex::new(KeyGate::new());
+static CONTEXT_TRACKER: Mutex<ContextTracker> = Mutex::new(ContextTracker::new());
+static KEY_TRACKER: Mutex<KeyTracker> = Mutex::new(KeyTracker::new());
+static GAMEPAD: XInputGamepad = XInputGamepad::new();
+static GAMEPAD_TRACKER: Mutex<GamepadTracker> = Mutex::new(GamepadTracker::new());
+static ZOOM_CONTROLLER: Mutex<ZoomController> = Mutex::new(ZoomController::new());
+static BINDING_SELECTOR: Mutex<BindingSelector> = Mutex::new(BindingSelector::new());
+static ZOOM_STOPS: Mutex<ZoomStops> = Mutex::new(ZoomStops::new());
+// 縦・横ホイールの端数
+static WHEEL_ACCUMULATORS: Mutex<(WheelAccumulator, WheelAccumulator)> =
+    Mutex::new((WheelAccumulator::new(), WheelAccumulator::new()));
+static SAFE_MODE: AtomicBool = AtomicBool::new(false);
+static FOV_OVERWRITE_REPORTED: AtomicBool = AtomicBool::new(false);
+// detour_render_level を実行中のスレッド数（フック削除前に抜けるのを待つ）
+static ACTIVE_RENDER_CALLS: AtomicUsize = AtomicUsize::new(0);
+// 起動中のスキャンの再試行を終了時に打ち切る
+static STARTUP_CANCEL: CancelToken = CancelToken::new();
 
-const WM_MOUSEWHEEL: u32 = 0x020A;
+/// 横スクロール1ノッチあたりのアニメーション速度の変化量
+const ANIMATION_SPEED_STEP: f32 = 0.05;
 
+/// 終了時に実行中の renderLevel が抜けるのを待つ最大時間
+const RENDER_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
 
-const RENDER_LEVEL_SIG: &[u8] = &[
-    0x48, 0x8B, 0xC4, 0x48, 0x89, 0x58, 0x00, 0x55, 0x56, 0x57, 0x41, 0x54, 0x41, 0x55, 0x41, 0x56, 0x41, 0x57, 0x48, 0x8D, 0xA8, 0x00, 0x00, 0x00, 0x00, 0x48, 0x81, 0xEC, 0x00, 0x00, 0x00, 0x00, 0x0F, 0x29, 0x70, 0x00, 0x0F, 0x29, 0x78, 0x00, 0x44, 0x0F, 0x29, 0x40, 0x00, 0x44, 0x0F, 0x29, 0x48, 0x00, 0x48, 0x8B, 0x05, 0x00, 0x00, 0x00, 0x00, 0x48, 0x33, 0xC4, 0x48, 0x89, 0x85, 0x00, 0x00, 0x00, 0x00, 0x4D, 0x8B, 0xE8, 0x4C, 0x8B, 0xE2, 0x4C, 0x8B, 0xF9
-];
 
-const RENDER_LEVEL_MASK: &[u8] = &[
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF
-];
+static mut ZOOM_MODIFIER: f32 = 1.0;
+static mut CURRENT_ZOOM_LEVEL: f32 = 10.0;
+// ズーム開始前に記録したゲームのFOV（lock_dynamic_fov 用）
+static mut LOCKED_BASE: Option<FovPair> = None;
 
-#[repr(C)]
-struct LevelRenderer {
-    padding: [u8; 0x3F0],
-    player: *mut LevelRendererPlayer,
+/// これ以下の倍率はズームしていないとみなす
+const ZOOM_IDLE_EPSILON: f32 = 0.001;
+
+unsafe extern "C" fn detour_render_level(level_renderer: *mut c_void, screen_context: *mut c_void, unk: *mut c_void) {
+    ACTIVE_RENDER_CALLS.fetch_add(1, Ordering::SeqCst);
+    render_level(level_renderer, screen_context, unk);
+    ACTIVE_RENDER_CALLS.fetch_sub(1, Ordering::SeqCst);
 }
 
-#[repr(C)]
-struct LevelRendererPlayer {
-    padding_x: [u8; 0xF80],
-    fov_x: f32,
-    padding_y: [u8; 0xF94 - 0xF80 - 4],
-    fov_y: f32,
+unsafe fn render_level(level_renderer: *mut c_void, screen_context: *mut c_void, unk: *mut c_void) {
+    let original_addr = ORIGINAL_RENDER_LEVEL.load(Ordering::Relaxed);
+    if original_addr != 0 {
+        let original: extern "C" fn(*mut c_void, *mut c_void, *mut c_void) = std::mem::transmute(original_addr);
+        
+        // セーフモード中は何もしない（フックの無効化待ち）
+        let patch = if SAFE_MODE.load(Ordering::Relaxed) {
+            None
+        } else {
+            apply_zoom(level_renderer)
+        };
+        
+        // ズーム後のFOVは renderLevel の呼び出し中だけ適用し、呼び出し後に元に戻す
+        original(level_renderer, screen_context, unk);
+        
+        if let Some((mut fields, patch)) = patch {
+            if patch.restore(&mut fields) == RestoreOutcome::OverwrittenByGame
+                && !FOV_OVERWRITE_REPORTED.swap(true, Ordering::Relaxed)
+            {
+                logger::info("the game rewrote the FOV during renderLevel; leaving its value in place");
+            }
+        }
+    }
 }
 
-static mut ZOOM_MODIFIER: f32 = 1.0;
-static mut CURRENT_ZOOM_LEVEL: f32 = 10.0;
+/// フックが受け取った入力イベントを取り込み、このフレームの入力を返す
+fn read_frame_input(config: &ZoomConfig) -> FrameInput {
+    // キーボードフックを使えない場合はポーリングでイベントを作る
+    if !input_hooks::is_keyboard_hooked() {
+        let mut keys = config.chat_keys.clone();
+        keys.extend(zoom_keys(config));
+        input_hooks::poll_keys(&keys);
+    }
+    let mut tracker = KEY_TRACKER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    tracker.advance(&INPUT_EVENTS)
+}
+
+/// ゲームパッドを読み、このフレームの入力を返す（何も割り当てていなければ読まない）
+fn read_gamepad(config: &ZoomConfig) -> GamepadFrame {
+    let bindings = config.gamepad_bindings();
+    if bindings.is_unbound() {
+        return GamepadFrame::default();
+    }
+    let mut tracker = GAMEPAD_TRACKER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    tracker.update(GAMEPAD.read(), &bindings)
+}
+
+/// このフレームのズームの進み具合 (0.0 ~ 1.0)
+///
+/// `use_trigger` はゲームパッドのトリガーでズームしている（キーボードやプリセットのキーではない）場合に true。
+fn zoom_amount(config: &ZoomConfig, pressed: bool, use_trigger: bool, gamepad: &GamepadFrame) -> f32 {
+    let settings = AnalogSettings {
+        source: config.analog_zoom,
+        curve: config.analog_curve,
+        ramp: Duration::from_secs_f32(config.analog_ramp_seconds),
+        trigger_threshold: config.gamepad_trigger_threshold,
+    };
+    // キーボードで押している場合はトリガーの深さを使わない
+    let trigger = match config.gamepad_zoom_button {
+        Some(GamepadInput::Trigger(_)) if use_trigger => Some(gamepad.zoom_amount),
+        _ => None,
+    };
+    let zoom_input = ZoomInput { held: pressed, trigger, time: input::now() };
+    ZOOM_CONTROLLER
+        .lock()
+        .map(|mut controller| controller.amount(&settings, &zoom_input))
+        .unwrap_or(0.0)
+}
+
+/// ズームに使うキー（zoom_key, プリセット, 段階を切り替えるキー）
+fn zoom_keys(config: &ZoomConfig) -> Vec<i32> {
+    std::iter::once(config.zoom_key)
+        .chain(config.presets.iter().map(|preset| preset.key))
+        .chain(config.zoom_stop_key)
+        .collect()
+}
 
-/// マウスホイールのフックプロシージャ
-unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-    if code >= 0 && wparam.0 as u32 == WM_MOUSEWHEEL {
-        let mouse_struct = lparam.0 as *const MSLLHOOKSTRUCT;
-        if !mouse_struct.is_null() {
-            // mouseDataの上位ワードにホイールデルタが含まれている
-            let wheel_delta = ((*mouse_struct).mouseData >> 16) as i16 as i32;
-            // デルタを蓄積（120単位で1ノッチ）
-            SCROLL_DELTA.fetch_add(wheel_delta, Ordering::Relaxed);
+/// zoom_key の段階を更新し、現在の段階の番号を返す（段階を指定していなければ 0）
+fn update_zoom_stops(config: &ZoomConfig, input: &FrameInput, zoom_held: bool) -> usize {
+    let settings = StopSettings {
+        wrap: config.zoom_stops_wrap,
+        reset_on_release: config.zoom_stops_reset_on_release,
+        step_with_zoom_key: config.zoom_stop_key.is_none(),
+    };
+    let stop_pressed = config.zoom_stop_key.is_some_and(|key| input.was_pressed(key));
+    ZOOM_STOPS
+        .lock()
+        .map(|mut stops| stops.update(config.zoom_stops.len(), &settings, zoom_held, stop_pressed, input::now()))
+        .unwrap_or(0)
+}
+
+/// 設定に必要なフック
+fn hook_requirements(config: &ZoomConfig) -> HookRequirements {
+    let uses_wheel = config.scroll_adjustment || config.horizontal_scroll_action != HorizontalWheelAction::None;
+    HookRequirements::from_usage(uses_wheel, &zoom_keys(config))
+}
+
+/// ズーム中の縦・横ホイールのノッチ数（端数と不感帯を考慮）
+fn read_wheel_notches(input: &FrameInput, config: &ZoomConfig, zooming: bool) -> (f32, f32) {
+    let mut accumulators = WHEEL_ACCUMULATORS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !zooming {
+        accumulators.0.reset();
+        accumulators.1.reset();
+        return (0.0, 0.0);
+    }
+    (
+        accumulators.0.feed_events(&input.events, WheelAxis::Vertical, config.wheel_dead_zone),
+        accumulators.1.feed_events(&input.events, WheelAxis::Horizontal, config.wheel_dead_zone),
+    )
+}
+
+/// フォーカス・カーソル・チャットキーから現在の状態を推定
+fn current_input_context(config: &ZoomConfig, input: &FrameInput) -> InputContext {
+    let sample = ContextSample {
+        focused: FOCUS.is_game_focused(),
+        cursor_visible: foreground_focus::is_cursor_visible(),
+        chat_key_down: config.chat_keys.iter().any(|&key| input.is_active(key)),
+    };
+    CONTEXT_TRACKER
+        .lock()
+        .map(|mut tracker| tracker.update(sample))
+        .unwrap_or(InputContext::Unfocused)
+}
+
+/// 入力からズーム倍率を更新し、ゲームの元のFOVにズームを適用する
+///
+/// 書き込んだ場合は、呼び出し後に元に戻すためのフィールドと記録を返す。
+unsafe fn apply_zoom(level_renderer: *mut c_void) -> Option<(PlayerFov<'static>, FovPatch)> {
+    let offsets = OFFSETS.get()?;
+    
+    // LevelRenderer またはプレイヤーが null の場合は何もしない
+    let mut fields = PlayerFov::from_level_renderer(level_renderer as *mut u8, offsets).ok()?;
+    
+    // 設定を取得（ファイルが更新されていたら自動で再読み込み）
+    let config = get_config();
+    
+    // キーボードフックは他のアプリやチャットでの入力も拾うため、プレイ中（と設定で許可した画面）だけ受け付ける
+    // フレームの間に押して離した短いタップも、そのフレームは押されたものとして扱う
+    let input = read_frame_input(&config);
+    let gamepad = read_gamepad(&config);
+    let bindings = config.bindings();
+    let keyboard_down = input.is_active(config.zoom_key);
+    // ゲームパッドのズームボタンは zoom_key と同じ割り当てとして扱う
+    let mut pressed: Vec<bool> = bindings.iter().map(|binding| input.is_active(binding.key)).collect();
+    pressed[0] |= gamepad.zoom_held;
+    let context = current_input_context(&config, &input);
+    let suppression = SuppressionSettings { in_chat: config.suppress_in_chat, in_menus: config.suppress_in_menus };
+    let allowed = KEY_GATE
+        .lock()
+        .map(|mut gate| gate.filter(suppression.allows(context), pressed.contains(&true)))
+        .unwrap_or(false);
+    if !allowed {
+        pressed.fill(false);
+    }
+    
+    // 複数押されている場合は最後に押した割り当てを使い、離した後もその設定でズームを戻す
+    let (active, binding_index) = {
+        let mut selector = BINDING_SELECTOR.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let active = selector.update(&pressed);
+        (active, active.or(selector.last()).filter(|&index| index < bindings.len()).unwrap_or(0))
+    };
+    let mut binding = bindings[binding_index];
+    let is_zoom_key_pressed = active.is_some();
+    
+    // zoom_key に段階を指定した場合は現在の段階の倍率でズーム
+    let stop = update_zoom_stops(&config, &input, active == Some(0));
+    let using_stops = binding_index == 0 && !config.zoom_stops.is_empty();
+    if using_stops {
+        binding.zoom_mode = ZoomMode::Multiplier;
+        binding.zoom_level = config.zoom_stops[stop];
+    }
+    
+    // 保存されたズーム倍率・目標視野角を使用
+    CURRENT_ZOOM_LEVEL = binding.zoom_level;
+    let mut target_fov = binding.target_fov_degrees;
+    
+    // ズームキーの状態を更新（マウスフック用）
+    let wheel_settings = WheelSettings {
+        scroll_adjustment: config.scroll_adjustment,
+        consume_while_zooming: config.consume_scroll_while_zooming,
+        horizontal: config.horizontal_scroll_action,
+    };
+    input_hooks::publish_wheel_state(wheel_settings, is_zoom_key_pressed);
+    // 設定の再読み込みでスクロール調整などが変わったらマウスフックを設定・解除
+    input_hooks::set_requirements(hook_requirements(&config));
+    
+    // ズーム中にマウスホイールで倍率を調整（設定で有効な場合のみ、ズームしていない間のスクロールは捨てる）
+    let (mut notches, horizontal_notches) = read_wheel_notches(&input, &config, is_zoom_key_pressed);
+    // ゲームパッドのボタンは1回押すごとに1ノッチとして扱う
+    if is_zoom_key_pressed {
+        notches += gamepad.level_steps as f32;
+    }
+    if notches != 0.0 && config.scroll_adjustment && using_stops {
+        // 段階を指定した場合はスクロールでも段階を切り替える（設定には保存しない）
+        let stop = ZOOM_STOPS
+            .lock()
+            .map(|mut stops| stops.scroll(config.zoom_stops.len(), config.zoom_stops_wrap, notches))
+            .unwrap_or(stop);
+        CURRENT_ZOOM_LEVEL = config.zoom_stops[stop];
+    } else if notches != 0.0 && config.scroll_adjustment {
+        match binding.zoom_mode {
+            ZoomMode::Multiplier => {
+                CURRENT_ZOOM_LEVEL = (CURRENT_ZOOM_LEVEL + notches * config.scroll_step).clamp(1.0, 50.0);
+                save_zoom_level(binding_index, CURRENT_ZOOM_LEVEL, &config);
+            }
+            ZoomMode::TargetFov => {
+                // 上スクロールで視野角を狭める（ズームイン）
+                target_fov = (target_fov - notches * config.scroll_step).clamp(1.0, 110.0);
+                save_target_fov(binding_index, target_fov, &config);
+            }
         }
     }
+    if horizontal_notches != 0.0 && config.horizontal_scroll_action == HorizontalWheelAction::AnimationSpeed {
+        // 同じフレームで保存したズーム倍率を上書きしないよう、反映済みの設定から変更する
+        let mut updated = get_config();
+        updated.set_binding_animation_speed(
+            binding_index,
+            (binding.animation_speed + horizontal_notches * ANIMATION_SPEED_STEP).clamp(0.01, 1.0),
+        );
+        save_deferred(updated);
+    }
+    
+    // スクロールで変更した設定は落ち着いてから保存
+    flush_due_save();
     
-    let hook = HHOOK(MOUSE_HOOK.load(Ordering::Relaxed) as isize);
-    CallNextHookEx(hook, code, wparam, lparam)
+    let Ok(mut guard) = FOV_GUARD.lock() else {
+        return None;
+    };
+    // プレイヤーが切り替わったら前フレームの記録を破棄
+    if LAST_PLAYER.swap(fields.address(), Ordering::Relaxed) != fields.address() {
+        guard.reset();
+    }
+    
+    // 書き込む前にゲームが設定した値を検査
+    let base = fields.read();
+    if !handle_verdict(guard.check_read(base.0, base.1), &guard) {
+        return None;
+    }
+    
+    // 倍率の基準にするFOV（固定する場合はズーム開始前に記録したFOV）
+    let reference = if config.lock_dynamic_fov {
+        let zooming = is_zoom_key_pressed || ZOOM_MODIFIER > 1.0 + ZOOM_IDLE_EPSILON;
+        let locked_base = LOCKED_BASE;
+        // ズームしていない間は記録を更新し続け、ズーム中は記録した値を使う
+        let reference = match locked_base {
+            Some(locked) if zooming => locked,
+            _ => base,
+        };
+        LOCKED_BASE = Some(reference);
+        reference
+    } else {
+        base
+    };
+    
+    let zoom_multiplier = match binding.zoom_mode {
+        ZoomMode::Multiplier => CURRENT_ZOOM_LEVEL,
+        ZoomMode::TargetFov => {
+            fov_math::multiplier_for_vertical_target(reference, target_fov)
+        }
+    };
+    // アナログズームでは入力の量に応じて目標の倍率まで段階的にズームする
+    let use_trigger = active == Some(0) && !keyboard_down;
+    let amount = zoom_amount(&config, is_zoom_key_pressed, use_trigger, &gamepad);
+    let target = ZoomController::multiplier(amount, zoom_multiplier);
+    
+    if binding.smooth_animation {
+        // スムーズアニメーション有効時: 補間で滑らかにズーム
+        ZOOM_MODIFIER = ZOOM_MODIFIER + (target - ZOOM_MODIFIER) * binding.animation_speed;
+    } else {
+        // スムーズアニメーション無効時: 即座にズーム
+        ZOOM_MODIFIER = target;
+    }
+    
+    // 毎フレームゲームの元の値から計算するため倍率は累積しない
+    let zoomed = if config.lock_dynamic_fov {
+        // ズームしきった時のFOVを記録した基準から求め、ダッシュなどによるFOVの変化を打ち消す
+        // アニメーション中は進み具合に応じて現在のFOVと補間する
+        let full_multiplier =
+            fov_math::clamp_multiplier(reference, zoom_multiplier, config.min_fov_degrees);
+        let locked_zoomed = fov_math::apply_multiplier(reference, full_multiplier);
+        let progress = fov_math::zoom_progress(ZOOM_MODIFIER, zoom_multiplier);
+        fov_math::blend(base, locked_zoomed, progress)
+    } else {
+        // 最小視野角より狭くならないように制限
+        let multiplier = fov_math::clamp_multiplier(base, ZOOM_MODIFIER, config.min_fov_degrees);
+        fov_math::apply_multiplier(base, multiplier)
+    };
+    let patch = FovPatch::apply(&mut fields, base, zoomed);
+    
+    // 書き込んだ値を読み戻して検査
+    if !handle_verdict(guard.check_written(zoomed, fields.read()), &guard) {
+        patch.restore(&mut fields);
+        return None;
+    }
+    
+    Some((fields, patch))
 }
 
-/// マウスフック用のメッセージループスレッド
-fn start_mouse_hook_thread() {
-    thread::spawn(|| {
-        unsafe {
-            // マウスホイールフックをインストール
-            if let Ok(hook) = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), None, 0) {
-                MOUSE_HOOK.store(hook.0 as usize, Ordering::Relaxed);
-                
-                // メッセージループ（フックが動作するために必要）
-                let mut msg: MSG = std::mem::zeroed();
-                while GetMessageW(&mut msg, None, 0, 0).as_bool() {
-                    // メッセージを処理（特に何もしない）
-                }
+/// 検査結果を処理し、書き込みを続けてよいかどうかを返す
+fn handle_verdict(verdict: GuardVerdict, guard: &FovGuard) -> bool {
+    match verdict {
+        GuardVerdict::Plausible => true,
+        GuardVerdict::Skip(problem) => {
+            // 連続した異常の最初の1回だけ記録する
+            if guard.implausible_frames() == 1 {
+                logger::warn(&format!("implausible FOV, skipping zoom: {}", problem));
             }
+            false
+        }
+        GuardVerdict::SafeMode(problem) => {
+            enter_safe_mode(problem);
+            false
         }
-    });
+    }
 }
 
-unsafe extern "C" fn detour_render_level(level_renderer: *mut LevelRenderer, screen_context: *mut c_void, unk: *mut c_void) {
-    let original_addr = ORIGINAL_RENDER_LEVEL.load(Ordering::Relaxed);
-    if original_addr != 0 {
-        let original: extern "C" fn(*mut LevelRenderer, *mut c_void, *mut c_void) = std::mem::transmute(original_addr);
-        
-        original(level_renderer, screen_context, unk);
+/// 異常なFOVが続いたためフックを無効化する
+fn enter_safe_mode(problem: FovProblem) {
+    if SAFE_MODE.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    logger::error(&format!(
+        "FOV values stayed implausible ({}); the offsets probably do not match this game version. \
+         Entering safe mode and disabling the render level hook",
+        problem
+    ));
 
-        if !level_renderer.is_null() {
-             let player = (*level_renderer).player;
-             if !player.is_null() {
-                 // 設定を取得（ファイルが更新されていたら自動で再読み込み）
-                 let config = get_config();
-                 
-                 // 保存されたズーム倍率を使用
-                 CURRENT_ZOOM_LEVEL = config.zoom_level;
-                 
-                 let is_zoom_key_pressed = GetAsyncKeyState(config.zoom_key) & 0x8000u16 as i16 != 0;
-                 
-                 // ズームキーの状態を更新（フック用）
-                 ZOOM_KEY_PRESSED.store(is_zoom_key_pressed, Ordering::Relaxed);
-                 
-                 // ズーム中にマウスホイールで倍率を調整（設定で有効な場合のみ）
-                 if is_zoom_key_pressed && config.scroll_adjustment {
-                     let scroll_delta = SCROLL_DELTA.swap(0, Ordering::Relaxed);
-                     if scroll_delta != 0 {
-                         // 120単位でscroll_step変更（スクロール1ノッチ = 120）
-                         let zoom_change = (scroll_delta as f32 / 120.0) * config.scroll_step;
-                         CURRENT_ZOOM_LEVEL = (CURRENT_ZOOM_LEVEL + zoom_change).clamp(1.0, 50.0);
-                         save_zoom_level(CURRENT_ZOOM_LEVEL, &config);
-                     }
-                 } else if !is_zoom_key_pressed {
-                     // ズームキーが押されていない時はスクロールデルタをクリア
-                     SCROLL_DELTA.store(0, Ordering::Relaxed);
-                 }
-                 
-                 let target = if is_zoom_key_pressed { CURRENT_ZOOM_LEVEL } else { 1.0 };
-                 
-                 if config.smooth_animation {
-                     // スムーズアニメーション有効時: 補間で滑らかにズーム
-                     ZOOM_MODIFIER = ZOOM_MODIFIER + (target - ZOOM_MODIFIER) * config.animation_speed;
-                 } else {
-                     // スムーズアニメーション無効時: 即座にズーム
-                     ZOOM_MODIFIER = target;
-                 }
-                 
-                 (*player).fov_x *= ZOOM_MODIFIER;
-                 (*player).fov_y *= ZOOM_MODIFIER;
-             }
-        }
+    let _ = LIFECYCLE.degrade(format!("implausible FOV values ({})", problem));
+    
+    // フック中の関数内からは無効化せず、別スレッドで行う
+    let target = RENDER_LEVEL_TARGET.load(Ordering::Relaxed);
+    if target != 0 {
+        thread::spawn(move || unsafe {
+            if let Err(error) = HOOKS.disable(target) {
+                logger::error(&format!("failed to disable the render level hook: {}", error));
+            }
+        });
     }
 }
 
-/// ズーム倍率を設定ファイルに保存
-fn save_zoom_level(zoom_level: f32, current_config: &ZoomConfig) {
+/// 割り当てのズーム倍率を設定ファイルに保存
+fn save_zoom_level(binding_index: usize, zoom_level: f32, current_config: &ZoomConfig) {
     let mut config = current_config.clone();
-    config.zoom_level = zoom_level;
-    let _ = config.save();
+    config.set_binding_zoom_level(binding_index, zoom_level);
+    save_deferred(config);
 }
 
-pub unsafe fn initialize() {
+/// 割り当ての目標視野角を設定ファイルに保存
+fn save_target_fov(binding_index: usize, target_fov_degrees: f32, current_config: &ZoomConfig) {
+    let mut config = current_config.clone();
+    config.set_binding_target_fov(binding_index, target_fov_degrees);
+    save_deferred(config);
+}
+
+/// 設定とオフセットを読み込み、renderLevel をフックする
+///
+/// # Safety
+/// ゲームのプロセス内で、起動用のスレッドから1回だけ呼び出すこと。
+pub unsafe fn initialize() -> Result<(), StartupError> {
     // 設定を初期化
-    init_config();
+    let config = init_config();
     
-    // マウスホイールフックを別スレッドで開始
-    start_mouse_hook_thread();
+    // ゲームバージョンを検出し、対応するオフセットを読み込み（offsets.yml があれば上書き）
+    let game_version = version::detect();
+    let offsets = OFFSETS.get_or_init(|| offsets::load(game_version));
     
     let base = windows::Win32::System::LibraryLoader::GetModuleHandleA(None).unwrap();
     
@@ -151,36 +448,146 @@ pub unsafe fn initialize() {
     let size_of_image = (*nt_headers).OptionalHeader.SizeOfImage as usize;
     
     let memory_slice = std::slice::from_raw_parts(base.0 as *const u8, size_of_image);
+    let image_base = base.0 as usize;
     
-    if let Some(offset) = find_pattern(memory_slice, RENDER_LEVEL_SIG, RENDER_LEVEL_MASK) {
-        let target_addr = (base.0 as usize + offset) as *mut c_void;
-        
+    // 前回起動時と同じイメージであればキャッシュ済みのアドレスを使用
+    let mut hook_cache = HookCache::load(memory_slice);
+    let name = offsets.render_level.name;
+    
+    // 関数の先頭に一致するシグネチャは他のModのフックで一致しなくなるため、スキャンの前に前回の位置を確認し、
+    // 書き換えられていればその位置をそのまま使う（デトゥアへの対応は作成時に設定に従う）
+    let cached_detoured = hook_cache
+        .cached_function_rva(name)
+        .filter(|&rva| prologue::detect_detour(memory_slice, image_base, rva).is_some());
+    let rva = match cached_detoured {
+        Some(rva) => rva,
+        None => resolve_render_level(&mut hook_cache, offsets, &config, memory_slice, image_base)?.rva,
+    };
+    
+    // 初期化中に終了した場合はフックを作成しない
+    if LIFECYCLE.state() == LifecycleState::ShutDown || STARTUP_CANCEL.is_cancelled() {
+        return Err(StartupError::Cancelled);
+    }
+    
+    // 他のModがすでにフックしている場合は設定に従い、デトゥアが呼ばれる前に元の関数のアドレスを保存してから有効化する
+    let target = image_base + rva;
+    let original = HOOKS.create_checked(
+        name,
+        memory_slice,
+        image_base,
+        rva,
+        detour_render_level as *const () as usize,
+        config.foreign_detour,
+    )?;
+    ORIGINAL_RENDER_LEVEL.store(original, Ordering::Relaxed);
+    RENDER_LEVEL_TARGET.store(target, Ordering::Relaxed);
+    HOOKS.enable(target).map_err(|error| StartupError::Install { name, error })?;
+    
+    // キーボード・マウスフックを別スレッドで開始
+    input_hooks::start(hook_requirements(&config));
+    Ok(())
+}
 
-        
-        let mut original: *mut c_void = std::ptr::null_mut();
-        if MH_CreateHook(target_addr, detour_render_level as *mut c_void, &mut original) == MH_OK {
-            ORIGINAL_RENDER_LEVEL.store(original as usize, Ordering::Relaxed);
-            MH_EnableHook(target_addr);
+/// renderLevel のシグネチャをスキャンする（ゲームの展開が終わっていない場合に備えて再試行する）
+fn resolve_render_level(
+    hook_cache: &mut HookCache,
+    offsets: &GameOffsets,
+    config: &ZoomConfig,
+    memory_slice: &[u8],
+    image_base: usize,
+) -> Result<ResolvedTarget, StartupError> {
+    let target = &offsets.render_level;
+    let policy = RetryPolicy::with_timeout(Duration::from_secs_f32(config.hook_retry_timeout_seconds));
+    let result = retry::retry(
+        &policy,
+        |attempt| {
+            if LIFECYCLE.state() == LifecycleState::ShutDown || STARTUP_CANCEL.is_cancelled() {
+                return Err(StartupError::Cancelled);
+            }
+            if attempt > 0 {
+                logger::info(&format!("{}: scan attempt {}", target.name, attempt + 1));
+            }
+            hook_cache
+                .resolve(target, memory_slice, image_base)
+                .ok_or(StartupError::TargetNotFound(target.name))
+        },
+        |error| matches!(error, StartupError::TargetNotFound(_)),
+        |delay| {
+            logger::info(&format!(
+                "{}: not found yet, retrying in {} ms",
+                target.name,
+                delay.as_millis()
+            ));
+            // 終了時は待ちを打ち切り、次の試行で Cancelled を返す
+            STARTUP_CANCEL.wait(delay);
+        },
+    );
+    hook_cache.save();
+    
+    match result {
+        Ok(resolved) => {
+            target.report_resolved(&resolved);
+            Ok(resolved)
+        }
+        Err(error) => {
+            if matches!(error, StartupError::TargetNotFound(_)) {
+                target.report_failure();
+            }
+            Err(error)
         }
     }
 }
 
-fn find_pattern(data: &[u8], pattern: &[u8], mask: &[u8]) -> Option<usize> {
-    if pattern.len() != mask.len() {
-        return None;
+/// 起動中のスキャンの待ちを打ち切る（ローダーロック中でも呼べる）
+pub fn cancel_startup() {
+    STARTUP_CANCEL.cancel();
+}
+
+/// フックを外し、入力フックを解除し、保留中の設定を保存する
+///
+/// 実行中の renderLevel を待ってからフックを削除し、削除できたら true を返す。
+/// 待ちきれなかった場合はフックを無効化したまま残し、false を返す。
+/// 2回目以降の呼び出しは何もせず、前回の結果を返す。
+///
+/// # Safety
+/// ゲームのプロセス内で、ローダーロックの外から呼び出すこと。
+/// 描画スレッドからは呼び出さないこと（描画の終了を待つため）。
+pub unsafe fn shutdown() -> bool {
+    cancel_startup();
+    if LIFECYCLE.transition(LifecycleState::ShutDown, None).is_err() {
+        return RENDER_LEVEL_TARGET.load(Ordering::Relaxed) == 0;
     }
     
-    for i in 0..data.len() - pattern.len() {
-        let mut found = true;
-        for j in 0..pattern.len() {
-            if mask[j] == 0xFF && data[i + j] != pattern[j] {
-                found = false;
-                break;
-            }
+    // 先にフックを無効化し、実行中の renderLevel がトランポリンを抜けるのを待ってから削除する
+    let target = RENDER_LEVEL_TARGET.load(Ordering::Relaxed);
+    if target != 0 {
+        if let Err(error) = HOOKS.disable(target) {
+            logger::warn(&format!("failed to disable the render level hook: {}", error));
         }
-        if found {
-            return Some(i);
+    }
+    let started = Instant::now();
+    let mut drained = true;
+    while ACTIVE_RENDER_CALLS.load(Ordering::SeqCst) != 0 {
+        if started.elapsed() >= RENDER_DRAIN_TIMEOUT {
+            drained = false;
+            break;
         }
+        thread::sleep(Duration::from_millis(1));
+    }
+    
+    // キーボード・マウスフックを解除する
+    input_hooks::stop();
+    flush_pending_save();
+    
+    if !drained {
+        logger::error("renderLevel is still running; leaving the disabled hook in place, do not unload the DLL");
+        return false;
+    }
+    if let Err(error) = HOOKS.detach() {
+        logger::warn(&format!("failed to remove hooks: {}", error));
+        return false;
     }
-    None
+    ORIGINAL_RENDER_LEVEL.store(0, Ordering::Relaxed);
+    RENDER_LEVEL_TARGET.store(0, Ordering::Relaxed);
+    true
 }
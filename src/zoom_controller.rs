@@ -0,0 +1,532 @@
+use std::time::Duration;
+
+/// アナログズームの入力
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalogSource {
+    /// 押している間は常に最大までズーム
+    Off,
+    /// ゲームパッドのトリガーの押し込みの深さに応じてズーム
+    Trigger,
+    /// 押し続けた時間に応じて最大までズーム
+    Hold,
+}
+
+impl AnalogSource {
+    /// 設定値を入力に変換（不明な値は off）
+    pub fn parse(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "trigger" => Self::Trigger,
+            "hold" => Self::Hold,
+            _ => Self::Off,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Trigger => "trigger",
+            Self::Hold => "hold",
+        }
+    }
+}
+
+/// 入力の量 (0.0 ~ 1.0) からズームの進み具合への変換
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalogCurve {
+    Linear,
+    /// 最初はゆっくり、最後に大きく変化する
+    EaseIn,
+    /// 最初に大きく、最後はゆっくり変化する
+    EaseOut,
+    /// 始まりと終わりをゆっくり変化させる
+    Smooth,
+}
+
+impl AnalogCurve {
+    /// 設定値を曲線に変換（不明な値は linear）
+    pub fn parse(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "ease_in" => Self::EaseIn,
+            "ease_out" => Self::EaseOut,
+            "smooth" => Self::Smooth,
+            _ => Self::Linear,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::EaseIn => "ease_in",
+            Self::EaseOut => "ease_out",
+            Self::Smooth => "smooth",
+        }
+    }
+
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::Smooth => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// アナログズームの設定
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnalogSettings {
+    pub source: AnalogSource,
+    pub curve: AnalogCurve,
+    /// Hold で最大までズームするまでの時間
+    pub ramp: Duration,
+    /// Trigger でズームを始める押し込みの深さ (0.0 ~ 1.0)
+    pub trigger_threshold: f32,
+}
+
+/// 1フレームのズームの入力
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomInput {
+    /// ズームの入力が押されている（抑制を適用済み）
+    pub held: bool,
+    /// トリガーの押し込みの深さ（トリガー以外で押されている場合は None）
+    pub trigger: Option<f32>,
+    /// フレームの時刻
+    pub time: Duration,
+}
+
+/// 入力からズームの進み具合を決める
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZoomController {
+    held_since: Option<Duration>,
+}
+
+impl ZoomController {
+    pub const fn new() -> Self {
+        Self { held_since: None }
+    }
+
+    /// ズームの進み具合 (0.0 = ズームなし, 1.0 = 最大までズーム)
+    pub fn amount(&mut self, settings: &AnalogSettings, input: &ZoomInput) -> f32 {
+        if !input.held {
+            self.held_since = None;
+            return 0.0;
+        }
+        let held_since = *self.held_since.get_or_insert(input.time);
+
+        match settings.source {
+            AnalogSource::Off => 1.0,
+            AnalogSource::Trigger => match input.trigger {
+                // しきい値から最後まで押し込むまでを 0.0 ~ 1.0 にする
+                Some(depth) if settings.trigger_threshold < 1.0 => settings
+                    .curve
+                    .apply((depth - settings.trigger_threshold) / (1.0 - settings.trigger_threshold)),
+                _ => 1.0,
+            },
+            AnalogSource::Hold => {
+                if settings.ramp.is_zero() {
+                    return 1.0;
+                }
+                let elapsed = input.time.saturating_sub(held_since);
+                settings.curve.apply(elapsed.as_secs_f32() / settings.ramp.as_secs_f32())
+            }
+        }
+    }
+
+    /// 進み具合に応じた倍率（倍率の比で補間し、どの段階でも同じ操作で同じだけ変化させる）
+    pub fn multiplier(amount: f32, full_multiplier: f32) -> f32 {
+        if amount <= 0.0 || full_multiplier <= 0.0 {
+            return 1.0;
+        }
+        full_multiplier.powf(amount.min(1.0))
+    }
+}
+
+/// 複数のズームの割り当てから使用するものを選ぶ
+///
+/// 複数押されている場合は最後に押したものを使い、それを離すと押したままの中で次に新しいものに戻る。
+/// 同じフレームで押されたものは番号の小さい順に押されたものとして扱う。
+#[derive(Clone, Debug, Default)]
+pub struct BindingSelector {
+    /// 押されている割り当ての番号（押された順）
+    held: Vec<usize>,
+    /// 最後に使用した割り当て（離した後のアニメーションに使用）
+    last: Option<usize>,
+}
+
+impl BindingSelector {
+    pub const fn new() -> Self {
+        Self { held: Vec::new(), last: None }
+    }
+
+    /// 各割り当てが押されているかを渡し、使用する割り当ての番号を返す（押されていなければ None）
+    pub fn update(&mut self, pressed: &[bool]) -> Option<usize> {
+        self.held.retain(|&index| pressed.get(index).copied().unwrap_or(false));
+        for (index, _) in pressed.iter().enumerate().filter(|(_, &down)| down) {
+            if !self.held.contains(&index) {
+                self.held.push(index);
+            }
+        }
+        let active = self.held.last().copied();
+        if active.is_some() {
+            self.last = active;
+        }
+        active
+    }
+
+    /// 押されていない間も含めて最後に使用した割り当て
+    pub fn last(&self) -> Option<usize> {
+        self.last
+    }
+}
+
+/// ズームの段階の切り替え方
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StopSettings {
+    /// 最後の段階の次は最初の段階に戻る（false なら最後の段階で止まる）
+    pub wrap: bool,
+    /// ズームをやめたら最初の段階に戻る
+    pub reset_on_release: bool,
+    /// ズームキーを押すたびに次の段階に進む（false なら別のキーで進める）
+    pub step_with_zoom_key: bool,
+}
+
+/// 決まった倍率の一覧（双眼鏡の倍率切り替えのような段階）から現在の段階を選ぶ
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZoomStops {
+    index: usize,
+    zoom_held: bool,
+    /// 最後にズームをやめた時刻（一度もズームしていなければ None）
+    released_at: Option<Duration>,
+    /// 段階の切り替えに満たないスクロール量
+    pending_notches: f32,
+}
+
+impl ZoomStops {
+    /// ズームキーで切り替える場合、離してからこの時間内に押し直すと次の段階に進む
+    pub const REPRESS_WINDOW: Duration = Duration::from_millis(400);
+
+    pub const fn new() -> Self {
+        Self { index: 0, zoom_held: false, released_at: None, pending_notches: 0.0 }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// ズームの入力と段階を切り替えるキーの押下を渡し、現在の段階の番号を返す
+    ///
+    /// `stop_pressed` は別のキーで切り替える場合にそのキーがこのフレームで押されたか。
+    pub fn update(
+        &mut self,
+        count: usize,
+        settings: &StopSettings,
+        zoom_held: bool,
+        stop_pressed: bool,
+        time: Duration,
+    ) -> usize {
+        if count == 0 {
+            *self = Self::new();
+            return 0;
+        }
+        self.index = self.index.min(count - 1);
+
+        let pressed = zoom_held && !self.zoom_held;
+        let released = !zoom_held && self.zoom_held;
+        self.zoom_held = zoom_held;
+
+        if settings.step_with_zoom_key {
+            if pressed {
+                let repressed = self
+                    .released_at
+                    .is_some_and(|released_at| time.saturating_sub(released_at) <= Self::REPRESS_WINDOW);
+                // 最初のズームは最初の段階から始める
+                if self.released_at.is_some() && (repressed || !settings.reset_on_release) {
+                    self.advance(count, settings.wrap, 1);
+                } else {
+                    self.index = 0;
+                }
+            }
+        } else if zoom_held && stop_pressed {
+            self.advance(count, settings.wrap, 1);
+        }
+
+        if released {
+            self.released_at = Some(time);
+            self.pending_notches = 0.0;
+            // ズームキーで切り替える場合は押し直しの猶予が過ぎてから戻す
+            if settings.reset_on_release && !settings.step_with_zoom_key {
+                self.index = 0;
+            }
+        }
+        self.index
+    }
+
+    /// スクロールのノッチ数だけ段階を進める（上スクロールで倍率の大きい方へ）
+    pub fn scroll(&mut self, count: usize, wrap: bool, notches: f32) -> usize {
+        if count == 0 {
+            return 0;
+        }
+        self.pending_notches += notches;
+        let steps = self.pending_notches.trunc();
+        self.pending_notches -= steps;
+        self.advance(count, wrap, steps as i32);
+        self.index
+    }
+
+    fn advance(&mut self, count: usize, wrap: bool, steps: i32) {
+        let next = self.index as i64 + steps as i64;
+        let last = count as i64 - 1;
+        self.index = if wrap { next.rem_euclid(count as i64) } else { next.clamp(0, last) } as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    fn analog(source: AnalogSource, curve: AnalogCurve, ramp: Duration, trigger_threshold: f32) -> AnalogSettings {
+        AnalogSettings { source, curve, ramp, trigger_threshold }
+    }
+
+    fn held(time: Duration, trigger: Option<f32>) -> ZoomInput {
+        ZoomInput { held: true, trigger, time }
+    }
+
+    fn released(time: Duration) -> ZoomInput {
+        ZoomInput { held: false, trigger: None, time }
+    }
+
+    #[test]
+    fn curves_keep_endpoints_and_clamp() {
+        for curve in [AnalogCurve::Linear, AnalogCurve::EaseIn, AnalogCurve::EaseOut, AnalogCurve::Smooth] {
+            assert_eq!(curve.apply(0.0), 0.0, "{:?}", curve);
+            assert_eq!(curve.apply(1.0), 1.0, "{:?}", curve);
+            assert_eq!(curve.apply(-0.5), 0.0, "{:?}", curve);
+            assert_eq!(curve.apply(2.0), 1.0, "{:?}", curve);
+            assert_eq!(curve.apply(f32::NAN), 0.0, "{:?}", curve);
+        }
+        assert!((AnalogCurve::Linear.apply(0.5) - 0.5).abs() < EPSILON);
+        assert!((AnalogCurve::EaseIn.apply(0.5) - 0.25).abs() < EPSILON);
+        assert!((AnalogCurve::EaseOut.apply(0.5) - 0.75).abs() < EPSILON);
+        assert!((AnalogCurve::Smooth.apply(0.5) - 0.5).abs() < EPSILON);
+        assert!(AnalogCurve::Smooth.apply(0.25) < 0.25);
+    }
+
+    #[test]
+    fn off_zooms_fully_while_held() {
+        let settings = analog(AnalogSource::Off, AnalogCurve::Linear, ms(500), 0.5);
+        let mut controller = ZoomController::new();
+        assert_eq!(controller.amount(&settings, &held(ms(0), Some(0.1))), 1.0);
+        assert_eq!(controller.amount(&settings, &released(ms(10))), 0.0);
+    }
+
+    #[test]
+    fn trigger_is_remapped_above_the_threshold() {
+        let settings = analog(AnalogSource::Trigger, AnalogCurve::Linear, ms(0), 0.2);
+        let mut controller = ZoomController::new();
+        assert!(controller.amount(&settings, &held(ms(0), Some(0.2))).abs() < EPSILON);
+        assert!((controller.amount(&settings, &held(ms(10), Some(0.6))) - 0.5).abs() < EPSILON);
+        assert!((controller.amount(&settings, &held(ms(20), Some(1.0))) - 1.0).abs() < EPSILON);
+
+        // 曲線は再マップ後の値に適用する
+        let eased = analog(AnalogSource::Trigger, AnalogCurve::EaseIn, ms(0), 0.2);
+        assert!((controller.amount(&eased, &held(ms(30), Some(0.6))) - 0.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn trigger_without_depth_or_full_threshold_zooms_fully() {
+        let settings = analog(AnalogSource::Trigger, AnalogCurve::Linear, ms(0), 0.2);
+        let mut controller = ZoomController::new();
+        // キーボードなどトリガー以外で押している場合
+        assert_eq!(controller.amount(&settings, &held(ms(0), None)), 1.0);
+
+        let full = analog(AnalogSource::Trigger, AnalogCurve::Linear, ms(0), 1.0);
+        assert_eq!(controller.amount(&full, &held(ms(10), Some(1.0))), 1.0);
+    }
+
+    #[test]
+    fn hold_ramps_from_the_first_held_frame() {
+        let settings = analog(AnalogSource::Hold, AnalogCurve::Linear, ms(400), 0.0);
+        let mut controller = ZoomController::new();
+        assert_eq!(controller.amount(&settings, &held(ms(1000), None)), 0.0);
+        assert!((controller.amount(&settings, &held(ms(1100), None)) - 0.25).abs() < EPSILON);
+        assert!((controller.amount(&settings, &held(ms(1200), None)) - 0.5).abs() < EPSILON);
+        assert_eq!(controller.amount(&settings, &held(ms(1400), None)), 1.0);
+        assert_eq!(controller.amount(&settings, &held(ms(2000), None)), 1.0);
+
+        // 離すと最初からやり直す
+        assert_eq!(controller.amount(&settings, &released(ms(2100))), 0.0);
+        assert_eq!(controller.amount(&settings, &held(ms(3000), None)), 0.0);
+        assert!((controller.amount(&settings, &held(ms(3100), None)) - 0.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn zero_ramp_zooms_immediately() {
+        let settings = analog(AnalogSource::Hold, AnalogCurve::Smooth, Duration::ZERO, 0.0);
+        let mut controller = ZoomController::new();
+        assert_eq!(controller.amount(&settings, &held(ms(0), None)), 1.0);
+    }
+
+    #[test]
+    fn multiplier_interpolates_geometrically() {
+        assert_eq!(ZoomController::multiplier(0.0, 4.0), 1.0);
+        assert!((ZoomController::multiplier(0.5, 4.0) - 2.0).abs() < EPSILON);
+        assert!((ZoomController::multiplier(1.0, 4.0) - 4.0).abs() < EPSILON);
+        assert!((ZoomController::multiplier(3.0, 4.0) - 4.0).abs() < EPSILON);
+        assert_eq!(ZoomController::multiplier(0.5, 0.0), 1.0);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for source in [AnalogSource::Off, AnalogSource::Trigger, AnalogSource::Hold] {
+            assert_eq!(AnalogSource::parse(source.as_str()), source);
+        }
+        for curve in [AnalogCurve::Linear, AnalogCurve::EaseIn, AnalogCurve::EaseOut, AnalogCurve::Smooth] {
+            assert_eq!(AnalogCurve::parse(curve.as_str()), curve);
+        }
+        assert_eq!(AnalogSource::parse("bogus"), AnalogSource::Off);
+        assert_eq!(AnalogCurve::parse("bogus"), AnalogCurve::Linear);
+    }
+
+    fn stops(wrap: bool, reset_on_release: bool, step_with_zoom_key: bool) -> StopSettings {
+        StopSettings { wrap, reset_on_release, step_with_zoom_key }
+    }
+
+    #[test]
+    fn selector_uses_the_latest_press_and_falls_back() {
+        let mut selector = BindingSelector::new();
+        assert_eq!(selector.update(&[false, false, false]), None);
+        assert_eq!(selector.last(), None);
+
+        assert_eq!(selector.update(&[true, false, false]), Some(0));
+        assert_eq!(selector.update(&[true, false, true]), Some(2));
+        assert_eq!(selector.update(&[true, true, true]), Some(1));
+        // 最後に押したものを離すと、押したままの中で次に新しいものに戻る
+        assert_eq!(selector.update(&[true, false, true]), Some(2));
+        assert_eq!(selector.update(&[true, false, false]), Some(0));
+
+        assert_eq!(selector.update(&[false, false, false]), None);
+        assert_eq!(selector.last(), Some(0));
+    }
+
+    #[test]
+    fn selector_orders_same_frame_presses_by_index() {
+        let mut selector = BindingSelector::new();
+        assert_eq!(selector.update(&[false, true, true]), Some(2));
+        assert_eq!(selector.update(&[false, true, false]), Some(1));
+    }
+
+    #[test]
+    fn selector_forgets_bindings_removed_on_reload() {
+        let mut selector = BindingSelector::new();
+        selector.update(&[true, true, true]);
+        assert_eq!(selector.update(&[true, true]), Some(1));
+    }
+
+    #[test]
+    fn scroll_wraps_or_clamps() {
+        let mut wrapping = ZoomStops::new();
+        assert_eq!(wrapping.scroll(3, true, 1.0), 1);
+        assert_eq!(wrapping.scroll(3, true, 2.0), 0);
+        assert_eq!(wrapping.scroll(3, true, -1.0), 2);
+
+        let mut clamping = ZoomStops::new();
+        assert_eq!(clamping.scroll(3, false, 5.0), 2);
+        assert_eq!(clamping.scroll(3, false, 1.0), 2);
+        assert_eq!(clamping.scroll(3, false, -7.0), 0);
+    }
+
+    #[test]
+    fn scroll_keeps_fractional_notches() {
+        let mut zoom_stops = ZoomStops::new();
+        assert_eq!(zoom_stops.scroll(3, false, 0.5), 0);
+        assert_eq!(zoom_stops.scroll(3, false, 0.5), 1);
+        assert_eq!(zoom_stops.scroll(0, false, 1.0), 0);
+    }
+
+    #[test]
+    fn release_resets_the_scrolled_stop() {
+        let settings = stops(false, true, false);
+        let mut zoom_stops = ZoomStops::new();
+        zoom_stops.update(3, &settings, true, false, ms(0));
+        zoom_stops.scroll(3, false, 2.0);
+        assert_eq!(zoom_stops.update(3, &settings, true, false, ms(10)), 2);
+        assert_eq!(zoom_stops.update(3, &settings, false, false, ms(20)), 0);
+
+        // reset_on_release = false なら段階を保つ
+        let keep = stops(false, false, false);
+        let mut zoom_stops = ZoomStops::new();
+        zoom_stops.update(3, &keep, true, false, ms(0));
+        zoom_stops.scroll(3, false, 2.0);
+        assert_eq!(zoom_stops.update(3, &keep, false, false, ms(20)), 2);
+        assert_eq!(zoom_stops.update(3, &keep, true, false, ms(30)), 2);
+    }
+
+    #[test]
+    fn release_discards_pending_notches() {
+        let settings = stops(false, false, false);
+        let mut zoom_stops = ZoomStops::new();
+        zoom_stops.update(3, &settings, true, false, ms(0));
+        zoom_stops.scroll(3, false, 0.75);
+        zoom_stops.update(3, &settings, false, false, ms(10));
+        assert_eq!(zoom_stops.scroll(3, false, 0.5), 0);
+    }
+
+    #[test]
+    fn separate_stop_key_advances_only_while_zooming() {
+        let settings = stops(true, false, false);
+        let mut zoom_stops = ZoomStops::new();
+        assert_eq!(zoom_stops.update(2, &settings, false, true, ms(0)), 0);
+        assert_eq!(zoom_stops.update(2, &settings, true, true, ms(10)), 1);
+        assert_eq!(zoom_stops.update(2, &settings, true, false, ms(20)), 1);
+        assert_eq!(zoom_stops.update(2, &settings, true, true, ms(30)), 0);
+    }
+
+    #[test]
+    fn zoom_key_repress_within_window_advances() {
+        let settings = stops(true, true, true);
+        let mut zoom_stops = ZoomStops::new();
+        // 最初のズームは最初の段階
+        assert_eq!(zoom_stops.update(3, &settings, true, false, ms(0)), 0);
+        // 離した直後は段階を保つ（押し直しの猶予）
+        assert_eq!(zoom_stops.update(3, &settings, false, false, ms(100)), 0);
+        assert_eq!(zoom_stops.update(3, &settings, true, false, ms(100) + ZoomStops::REPRESS_WINDOW), 1);
+        assert_eq!(zoom_stops.update(3, &settings, false, false, ms(600)), 1);
+        assert_eq!(zoom_stops.update(3, &settings, true, false, ms(700)), 2);
+        assert_eq!(zoom_stops.update(3, &settings, false, false, ms(800)), 2);
+        // 猶予を過ぎて押すと最初の段階に戻る
+        assert_eq!(zoom_stops.update(3, &settings, true, false, ms(801) + ZoomStops::REPRESS_WINDOW), 0);
+    }
+
+    #[test]
+    fn zoom_key_without_reset_advances_on_every_press() {
+        let settings = stops(false, false, true);
+        let mut zoom_stops = ZoomStops::new();
+        assert_eq!(zoom_stops.update(2, &settings, true, false, ms(0)), 0);
+        zoom_stops.update(2, &settings, false, false, ms(10));
+        assert_eq!(zoom_stops.update(2, &settings, true, false, ms(5000)), 1);
+        zoom_stops.update(2, &settings, false, false, ms(5010));
+        // wrap = false なので最後の段階で止まる
+        assert_eq!(zoom_stops.update(2, &settings, true, false, ms(5020)), 1);
+    }
+
+    #[test]
+    fn shrinking_the_stop_list_clamps_the_index() {
+        let settings = stops(false, false, false);
+        let mut zoom_stops = ZoomStops::new();
+        zoom_stops.update(4, &settings, true, false, ms(0));
+        zoom_stops.scroll(4, false, 3.0);
+        assert_eq!(zoom_stops.index(), 3);
+
+        assert_eq!(zoom_stops.update(2, &settings, true, false, ms(10)), 1);
+        assert_eq!(zoom_stops.update(0, &settings, true, false, ms(20)), 0);
+        assert_eq!(zoom_stops.index(), 0);
+    }
+}